@@ -0,0 +1,387 @@
+// crates/deads-crank/src/lib.rs
+
+//! ============================================================================
+//! deads-crank — off-chain keeper for the Solana Deads fee router
+//! ----------------------------------------------------------------------------
+//! Assembles `harvest_and_distribute`'s `remaining_accounts` so callers no
+//! longer have to:
+//!   1) Scans Token-2022 for token accounts of the router's mint with
+//!      `getProgramAccounts` (memcmp on the mint and the account-type byte)
+//!   2) Keeps the ones with a non-zero `TransferFeeAmount.withheld_amount`
+//!   3) Packs them, largest first, into as few transactions as fit in a
+//!      packet, and submits (or, in dry-run, only simulates) each one
+//!   4) Sizes each transaction's compute-unit limit from a simulation,
+//!      halving any chunk that would need more than the per-chunk cap
+//!
+//! ============================================================================
+
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::AccountDeserialize;
+use anyhow::{bail, ensure, Context, Result};
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_rpc_client_api::filter::{Memcmp, RpcFilterType};
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::Transaction;
+use solanadeads_fee_router_client::{self as client, chunk_by, fit_compute, tx_size, Router, MAX_COMPUTE_UNIT_LIMIT};
+use spl_token_2022::extension::transfer_fee::TransferFeeAmount;
+use spl_token_2022::extension::{AccountType, BaseStateWithExtensions, StateWithExtensions};
+use spl_token_2022::state::Account as SplAccount;
+
+// ------------------------------ Discovery ------------------------------------
+
+/// Offset of the mint in a token account.
+const MINT_OFFSET: usize = 0;
+/// Offset of the account-type byte that follows the base account once a
+/// token account carries extensions.
+const ACCOUNT_TYPE_OFFSET: usize = SplAccount::LEN;
+
+/// A token account with withheld transfer fees.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeAccount {
+    pub address: Pubkey,
+    pub withheld: u64,
+}
+
+/// `getProgramAccounts` filters for Token-2022 token accounts of `mint`.
+/// Accounts without extensions (plain 165 bytes) cannot hold a
+/// `TransferFeeAmount`, so requiring the account-type byte drops them too.
+pub fn fee_account_filters(mint: &Pubkey) -> Vec<RpcFilterType> {
+    vec![
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(MINT_OFFSET, mint.as_ref())),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            ACCOUNT_TYPE_OFFSET,
+            &[AccountType::Account as u8],
+        )),
+    ]
+}
+
+/// Withheld transfer fees on a Token-2022 account of `mint` (`None` if
+/// `data` is not one, or has no `TransferFeeAmount` extension).
+pub fn withheld_amount(data: &[u8], mint: &Pubkey) -> Option<u64> {
+    let state = StateWithExtensions::<SplAccount>::unpack(data).ok()?;
+    if state.base.mint != *mint {
+        return None;
+    }
+    let fee = state.get_extension::<TransferFeeAmount>().ok()?;
+    Some(fee.withheld_amount.into())
+}
+
+/// Accounts holding at least `min_withheld` (and never zero), largest first
+/// so a capped run sweeps the most fees.
+pub fn select_fee_accounts(
+    accounts: impl IntoIterator<Item = (Pubkey, Vec<u8>)>,
+    mint: &Pubkey,
+    min_withheld: u64,
+) -> Vec<FeeAccount> {
+    let mut selected: Vec<FeeAccount> = accounts
+        .into_iter()
+        .filter_map(|(address, data)| {
+            let withheld = withheld_amount(&data, mint)?;
+            (withheld >= min_withheld.max(1)).then_some(FeeAccount { address, withheld })
+        })
+        .collect();
+    selected.sort_by(|a, b| b.withheld.cmp(&a.withheld).then(a.address.cmp(&b.address)));
+    selected
+}
+
+/// Scan the cluster for fee-bearing accounts of `mint`.
+pub fn scan_fee_accounts(rpc: &RpcClient, mint: &Pubkey, min_withheld: u64) -> Result<Vec<FeeAccount>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(fee_account_filters(mint)),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    let accounts = rpc
+        .get_program_accounts_with_config(&spl_token_2022::ID, config)
+        .context("getProgramAccounts on Token-2022")?;
+    Ok(select_fee_accounts(
+        accounts.into_iter().map(|(address, account)| (address, account.data)),
+        mint,
+        min_withheld,
+    ))
+}
+
+// ------------------------------ Transactions ---------------------------------
+
+/// Router state plus what a keeper needs to build its harvests.
+pub struct Crank {
+    pub mint: Pubkey,
+    pub router: Pubkey,
+    pub state: Router,
+    /// Forwarded to the rewards sync; empty means all active pools.
+    pub pool_ids: Vec<u8>,
+}
+
+/// A planned harvest and the compute-unit limit it is sent with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chunk {
+    pub accounts: Vec<FeeAccount>,
+    pub compute_unit_limit: u32,
+}
+
+/// Outcome of one chunk.
+#[derive(Debug)]
+pub enum Outcome {
+    Sent(Signature),
+    Simulated { units_consumed: Option<u64> },
+}
+
+impl Crank {
+    /// Fetch and decode the router for `mint`.
+    pub fn load(rpc: &RpcClient, mint: Pubkey, pool_ids: Vec<u8>) -> Result<Self> {
+        let router = client::router_pda(&mint);
+        let data = rpc
+            .get_account_data(&router)
+            .with_context(|| format!("router {router} for mint {mint}"))?;
+        let state = Router::try_deserialize(&mut data.as_slice()).context("decoding router")?;
+        Ok(Self {
+            mint,
+            router,
+            state,
+            pool_ids,
+        })
+    }
+
+    /// Refuse to crank when the router would reject `caller` anyway.
+    pub fn check_caller(&self, caller: &Pubkey) -> Result<()> {
+        ensure!(!self.state.paused, "router {} is paused", self.router);
+        ensure!(
+            self.state.can_crank(caller),
+            "{caller} is not the router authority or a crank operator, and permissionless mode is off"
+        );
+        Ok(())
+    }
+
+    /// `harvest_and_distribute` over `sources`, with the split table sinks
+    /// and (when the sync is enabled) the rewards-program accounts.
+    pub fn harvest_ix(&self, caller: &Pubkey, sources: &[Pubkey]) -> Instruction {
+        client::harvest_and_distribute(caller, &self.mint, &self.state, sources, self.pool_ids.clone())
+    }
+
+    /// `set_compute_unit_limit(units)` followed by the harvest.
+    pub fn harvest_ixs(&self, caller: &Pubkey, sources: &[Pubkey], units: u32) -> Vec<Instruction> {
+        vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(units),
+            self.harvest_ix(caller, sources),
+        ]
+    }
+
+    /// Split `accounts` into harvests that each fit in one packet and carry
+    /// at most `max_per_tx` sources. The limit's value does not change the
+    /// size, so the maximum stands in for the one `budget` picks later.
+    pub fn plan(&self, payer: &Pubkey, accounts: &[FeeAccount], max_per_tx: usize) -> Result<Vec<Vec<FeeAccount>>> {
+        chunk_by(accounts, max_per_tx, |chunk| {
+            tx_size(payer, &self.harvest_ixs(payer, &addresses(chunk), MAX_COMPUTE_UNIT_LIMIT)) <= PACKET_DATA_SIZE
+        })
+    }
+
+    /// Give each planned chunk a compute-unit limit from a simulation under
+    /// the maximum limit, halving chunks that would need more than
+    /// `max_units`.
+    pub fn budget(
+        &self,
+        rpc: &RpcClient,
+        payer: &Pubkey,
+        chunks: Vec<Vec<FeeAccount>>,
+        max_units: u32,
+    ) -> Result<Vec<Chunk>> {
+        let fitted = fit_compute(chunks, max_units, |chunk| {
+            client::simulate_units(rpc, payer, &self.harvest_ixs(payer, &addresses(chunk), MAX_COMPUTE_UNIT_LIMIT))
+        })?;
+        Ok(fitted
+            .into_iter()
+            .map(|(accounts, compute_unit_limit)| Chunk {
+                accounts,
+                compute_unit_limit,
+            })
+            .collect())
+    }
+
+    /// Submit one chunk, or only simulate it in dry-run.
+    pub fn execute(&self, rpc: &RpcClient, payer: &Keypair, chunk: &Chunk, dry_run: bool) -> Result<Outcome> {
+        let ixs = self.harvest_ixs(&payer.pubkey(), &addresses(&chunk.accounts), chunk.compute_unit_limit);
+        let blockhash = rpc.get_latest_blockhash()?;
+        let tx = Transaction::new_signed_with_payer(&ixs, Some(&payer.pubkey()), &[payer], blockhash);
+
+        if !dry_run {
+            return Ok(Outcome::Sent(rpc.send_and_confirm_transaction(&tx)?));
+        }
+        let sim = rpc.simulate_transaction(&tx)?.value;
+        if let Some(err) = sim.err {
+            bail!("simulation failed: {err}\n{}", sim.logs.unwrap_or_default().join("\n"));
+        }
+        Ok(Outcome::Simulated {
+            units_consumed: sim.units_consumed,
+        })
+    }
+}
+
+fn addresses(accounts: &[FeeAccount]) -> Vec<Pubkey> {
+    accounts.iter().map(|acc| acc.address).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solanadeads_fee_router::{SinkConfig, SplitRecipient};
+    use spl_token_2022::extension::{BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut};
+    use spl_token_2022::state::AccountState;
+
+    fn base_account(mint: Pubkey) -> SplAccount {
+        SplAccount {
+            mint,
+            owner: Pubkey::new_unique(),
+            state: AccountState::Initialized,
+            ..Default::default()
+        }
+    }
+
+    /// Token account for `mint` with a TransferFeeAmount extension.
+    fn fee_account_data(mint: Pubkey, withheld: u64) -> Vec<u8> {
+        let len = ExtensionType::try_calculate_account_len::<SplAccount>(&[ExtensionType::TransferFeeAmount])
+            .unwrap();
+        let mut data = vec![0; len];
+        let mut state = StateWithExtensionsMut::<SplAccount>::unpack_uninitialized(&mut data).unwrap();
+        state.init_extension::<TransferFeeAmount>(true).unwrap().withheld_amount = withheld.into();
+        state.base = base_account(mint);
+        state.pack_base();
+        state.init_account_type().unwrap();
+        data
+    }
+
+    fn crank(mint: Pubkey, rewards_enabled: bool) -> Crank {
+        let sinks = SinkConfig {
+            treasury_owner: Pubkey::new_unique(),
+            lp_owner: Pubkey::new_unique(),
+            stakers_wallet: Pubkey::new_unique(),
+        };
+        let mut state = Router::new(255, Pubkey::new_unique(), sinks, &mint, &spl_token_2022::ID);
+        state.rewards_cpi.enabled = rewards_enabled;
+        Crank {
+            mint,
+            router: Pubkey::new_unique(),
+            state,
+            pool_ids: vec![1, 2],
+        }
+    }
+
+    #[test]
+    fn filters_match_fee_accounts_of_the_mint() {
+        let matches = |filters: Vec<RpcFilterType>, data: &[u8]| {
+            filters.iter().all(|filter| match filter {
+                RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(data),
+                _ => false,
+            })
+        };
+        let mint = Pubkey::new_unique();
+        let data = fee_account_data(mint, 7);
+        assert!(matches(fee_account_filters(&mint), &data));
+        assert!(!matches(fee_account_filters(&Pubkey::new_unique()), &data));
+
+        // Plain accounts have no account-type byte to match
+        let mut plain = vec![0; SplAccount::LEN];
+        base_account(mint).pack_into_slice(&mut plain);
+        assert!(!matches(fee_account_filters(&mint), &plain));
+    }
+
+    #[test]
+    fn selects_withheld_accounts_largest_first() {
+        let mint = Pubkey::new_unique();
+        let mut plain = vec![0; SplAccount::LEN];
+        base_account(mint).pack_into_slice(&mut plain);
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let accounts = vec![
+            (a, fee_account_data(mint, 5)),
+            (b, fee_account_data(mint, 50)),
+            (c, fee_account_data(mint, 0)),
+            (Pubkey::new_unique(), fee_account_data(Pubkey::new_unique(), 99)),
+            (Pubkey::new_unique(), plain),
+            (Pubkey::new_unique(), vec![1, 2, 3]),
+        ];
+
+        assert_eq!(
+            select_fee_accounts(accounts.clone(), &mint, 0),
+            vec![
+                FeeAccount { address: b, withheld: 50 },
+                FeeAccount { address: a, withheld: 5 },
+            ]
+        );
+        assert_eq!(
+            select_fee_accounts(accounts, &mint, 6),
+            vec![FeeAccount { address: b, withheld: 50 }]
+        );
+    }
+
+    #[test]
+    fn harvest_ix_lists_sinks_then_sources() {
+        let mint = Pubkey::new_unique();
+        let crank = crank(mint, true);
+        let caller = Pubkey::new_unique();
+        let sources = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let ix = crank.harvest_ix(&caller, &sources);
+
+        let splits: Vec<Pubkey> = crank.state.splits.iter().map(|s: &SplitRecipient| s.recipient).collect();
+        let tail: Vec<Pubkey> = ix.accounts[ix.accounts.len() - 5..].iter().map(|m| m.pubkey).collect();
+        assert_eq!(&tail[..3], &splits[..]);
+        assert_eq!(&tail[3..], &sources[..]);
+        assert!(ix.accounts.iter().any(|m| m.pubkey == crank.state.rewards_cpi.program_id));
+
+        // With the sync disabled the optional rewards accounts are left out
+        // (Anchor fills the slots with the router program ID)
+        let ix = self::crank(mint, false).harvest_ix(&caller, &sources);
+        assert!(!ix.accounts.iter().any(|m| m.pubkey == solanadeads_fee_router::REWARDS_PROGRAM_ID));
+    }
+
+    #[test]
+    fn plan_fills_packets() {
+        let mint = Pubkey::new_unique();
+        let crank = crank(mint, true);
+        let payer = Pubkey::new_unique();
+        let accounts: Vec<FeeAccount> = (0..100)
+            .map(|i| FeeAccount {
+                address: Pubkey::new_unique(),
+                withheld: 1_000 - i,
+            })
+            .collect();
+
+        let chunks = crank.plan(&payer, &accounts, usize::MAX).unwrap();
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.concat(), accounts);
+        let size = |sources: &[Pubkey]| tx_size(&payer, &crank.harvest_ixs(&payer, sources, MAX_COMPUTE_UNIT_LIMIT));
+        for chunk in &chunks[..chunks.len() - 1] {
+            let sources = addresses(chunk);
+            assert!(size(&sources) <= PACKET_DATA_SIZE);
+            // One more source would have overflowed the packet
+            let mut more = sources.clone();
+            more.push(Pubkey::new_unique());
+            assert!(size(&more) > PACKET_DATA_SIZE);
+        }
+
+        let capped = crank.plan(&payer, &accounts, 4).unwrap();
+        assert_eq!(capped.len(), 25);
+    }
+
+    #[test]
+    fn harvest_ixs_set_the_compute_unit_limit_first() {
+        let crank = crank(Pubkey::new_unique(), true);
+        let caller = Pubkey::new_unique();
+        let sources = [Pubkey::new_unique()];
+        let ixs = crank.harvest_ixs(&caller, &sources, 250_000);
+        assert_eq!(
+            ixs,
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(250_000),
+                crank.harvest_ix(&caller, &sources),
+            ]
+        );
+    }
+}
@@ -0,0 +1,117 @@
+// crates/deads-crank/src/main.rs
+
+//! `deads-crank`: scan for fee-bearing accounts of a router's mint and sweep
+//! them with `harvest_and_distribute`, one packet-sized chunk at a time,
+//! each under a compute-unit limit sized from simulation.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use deads_crank::{scan_fee_accounts, Crank, Outcome};
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Signer};
+use solanadeads_fee_router::DEADS_MINT;
+use solanadeads_fee_router_client::MAX_COMPUTE_UNIT_LIMIT;
+
+#[derive(Parser)]
+#[command(name = "deads-crank", version, about)]
+struct Args {
+    /// RPC endpoint.
+    #[arg(long, short = 'u', env = "DEADS_CRANK_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+
+    /// Fee payer and crank caller (defaults to ~/.config/solana/id.json).
+    #[arg(long, short = 'k', env = "DEADS_CRANK_KEYPAIR")]
+    keypair: Option<PathBuf>,
+
+    /// Router mint.
+    #[arg(long, default_value = DEADS_MINT)]
+    mint: Pubkey,
+
+    /// Skip accounts with less than this withheld.
+    #[arg(long, default_value_t = 1)]
+    min_withheld: u64,
+
+    /// Cap on sources per transaction (the packet size caps it too).
+    #[arg(long, default_value_t = 64)]
+    max_accounts_per_tx: usize,
+
+    /// Cap on each transaction's compute-unit limit; chunks that need more are split.
+    #[arg(long, default_value_t = MAX_COMPUTE_UNIT_LIMIT)]
+    max_compute_units: u32,
+
+    /// Stop after this many transactions; the largest balances go first.
+    #[arg(long)]
+    max_transactions: Option<usize>,
+
+    /// Pool ids forwarded to the rewards sync (comma-separated; empty = all active pools).
+    #[arg(long, value_delimiter = ',')]
+    pool_ids: Vec<u8>,
+
+    /// Plan and simulate every transaction without sending any.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+fn default_keypair_path() -> PathBuf {
+    let home = std::env::var_os("HOME").unwrap_or_default();
+    PathBuf::from(home).join(".config/solana/id.json")
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let keypair_path = args.keypair.unwrap_or_else(default_keypair_path);
+    let payer = read_keypair_file(&keypair_path)
+        .map_err(|err| anyhow::anyhow!("{err}"))
+        .with_context(|| format!("reading keypair {}", keypair_path.display()))?;
+    let rpc = RpcClient::new_with_commitment(args.url, CommitmentConfig::confirmed());
+
+    let crank = Crank::load(&rpc, args.mint, args.pool_ids)?;
+    crank.check_caller(&payer.pubkey())?;
+
+    let accounts = scan_fee_accounts(&rpc, &args.mint, args.min_withheld)?;
+    let withheld: u64 = accounts.iter().map(|acc| acc.withheld).sum();
+    println!(
+        "{} fee-bearing accounts for {} ({} withheld)",
+        accounts.len(),
+        args.mint,
+        withheld
+    );
+
+    let mut planned = crank.plan(&payer.pubkey(), &accounts, args.max_accounts_per_tx)?;
+    // Truncated before and after budgeting: only chunks that may run are
+    // simulated, and halving can add chunks
+    if let Some(max) = args.max_transactions {
+        planned.truncate(max);
+    }
+    let mut chunks = crank.budget(&rpc, &payer.pubkey(), planned, args.max_compute_units)?;
+    if let Some(max) = args.max_transactions {
+        chunks.truncate(max);
+    }
+    for (i, chunk) in chunks.iter().enumerate() {
+        let withheld: u64 = chunk.accounts.iter().map(|acc| acc.withheld).sum();
+        let outcome = crank
+            .execute(&rpc, &payer, chunk, args.dry_run)
+            .with_context(|| format!("chunk {}/{}", i + 1, chunks.len()))?;
+        let result = match outcome {
+            Outcome::Sent(signature) => signature.to_string(),
+            Outcome::Simulated { units_consumed } => format!(
+                "simulated ok, {} CU",
+                units_consumed.map_or("?".to_string(), |cu| cu.to_string())
+            ),
+        };
+        println!(
+            "chunk {}/{}: {} accounts, {} withheld, {} CU limit: {}",
+            i + 1,
+            chunks.len(),
+            chunk.accounts.len(),
+            withheld,
+            chunk.compute_unit_limit,
+            result
+        );
+    }
+    Ok(())
+}
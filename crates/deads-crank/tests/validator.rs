@@ -0,0 +1,212 @@
+// crates/deads-crank/tests/validator.rs
+
+//! End-to-end run against a local `solana-test-validator` with the router
+//! deployed at its program ID:
+//!
+//! ```text
+//! anchor build
+//! solana-test-validator --reset \
+//!   --bpf-program DEADS3ucNHjN8iz3Cw65joYxgVdguNsjytHRqCs7QvzA target/deploy/solanadeads_fee_router.so
+//! cargo test -p deads-crank --test validator -- --ignored
+//! ```
+//!
+//! Set `DEADS_CRANK_TEST_RPC` to point at another validator. The test makes
+//! its own fee-bearing mint, router and holders, so it can share a ledger
+//! with earlier runs.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::spl_associated_token_account::instruction::create_associated_token_account;
+use deads_crank::{scan_fee_accounts, Crank, Outcome};
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_system_interface::instruction as system_instruction;
+use solanadeads_fee_router::REWARDS_PROGRAM_ID;
+use solanadeads_fee_router_client::{
+    self as client, token_account as ata, RewardsCpiConfig, SinkConfig, MAX_COMPUTE_UNIT_LIMIT,
+};
+use spl_token_2022::extension::transfer_fee::instruction as fee_ix;
+use spl_token_2022::extension::{ExtensionType, StateWithExtensions};
+use spl_token_2022::state::{Account as SplAccount, Mint as SplMint};
+
+const DECIMALS: u8 = 6;
+const FEE_BPS: u16 = 100; // 1.00%
+const TRANSFER: u64 = 1_000_000;
+const FEE: u64 = 10_000;
+/// Enough holders to need more than one packet.
+const HOLDERS: usize = 40;
+
+fn send(rpc: &RpcClient, payer: &Keypair, ixs: &[Instruction], signers: &[&Keypair]) {
+    let mut all = vec![payer];
+    all.extend_from_slice(signers);
+    let blockhash = rpc.get_latest_blockhash().unwrap();
+    let tx = Transaction::new_signed_with_payer(ixs, Some(&payer.pubkey()), &all, blockhash);
+    rpc.send_and_confirm_transaction(&tx).unwrap();
+}
+
+fn balance(rpc: &RpcClient, token_account: &Pubkey) -> u64 {
+    let data = rpc.get_account_data(token_account).unwrap();
+    StateWithExtensions::<SplAccount>::unpack(&data).unwrap().base.amount
+}
+
+/// Fee-bearing mint whose withdraw authority is the router PDA, with sink
+/// ATAs and an initialized router. The rewards sync is turned off because
+/// the rewards program is not deployed on the test validator.
+fn setup_router(rpc: &RpcClient, payer: &Keypair) -> (Pubkey, SinkConfig) {
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
+    let router = client::router_pda(&mint);
+    let space = ExtensionType::try_calculate_account_len::<SplMint>(&[ExtensionType::TransferFeeConfig]).unwrap();
+    let rent = rpc.get_minimum_balance_for_rent_exemption(space).unwrap();
+    send(
+        rpc,
+        payer,
+        &[
+            system_instruction::create_account(&payer.pubkey(), &mint, rent, space as u64, &spl_token_2022::ID),
+            fee_ix::initialize_transfer_fee_config(
+                &spl_token_2022::ID,
+                &mint,
+                Some(&payer.pubkey()),
+                Some(&router),
+                FEE_BPS,
+                u64::MAX,
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint2(&spl_token_2022::ID, &mint, &payer.pubkey(), None, DECIMALS)
+                .unwrap(),
+        ],
+        &[&mint_kp],
+    );
+
+    let vault_authority = client::rewards_vault_authority(&mint, &REWARDS_PROGRAM_ID);
+    let sinks = client::default_sinks(Pubkey::new_unique(), Pubkey::new_unique(), &mint);
+    let ixs: Vec<Instruction> = [router, sinks.treasury_owner, sinks.lp_owner, vault_authority]
+        .iter()
+        .map(|owner| create_associated_token_account(&payer.pubkey(), owner, &mint, &spl_token_2022::ID))
+        .collect();
+    send(rpc, payer, &ixs, &[]);
+
+    let init = client::initialize_router(&payer.pubkey(), &mint, sinks);
+    let disable_sync = Instruction {
+        program_id: solanadeads_fee_router::ID,
+        accounts: solanadeads_fee_router::accounts::UpdateRewardsCpi {
+            router,
+            authority: payer.pubkey(),
+            mint,
+        }
+        .to_account_metas(None),
+        data: solanadeads_fee_router::instruction::UpdateRewardsCpi {
+            rewards_cpi: RewardsCpiConfig {
+                enabled: false,
+                ..RewardsCpiConfig::default()
+            },
+        }
+        .data(),
+    };
+    send(rpc, payer, &[init, disable_sync], &[]);
+    (mint, sinks)
+}
+
+/// `HOLDERS` ATAs that each received `TRANSFER`, so each withholds `FEE`.
+fn fee_bearing_accounts(rpc: &RpcClient, payer: &Keypair, mint: &Pubkey) -> Vec<Pubkey> {
+    let sender = ata(&payer.pubkey(), mint);
+    send(
+        rpc,
+        payer,
+        &[
+            create_associated_token_account(&payer.pubkey(), &payer.pubkey(), mint, &spl_token_2022::ID),
+            spl_token_2022::instruction::mint_to(
+                &spl_token_2022::ID,
+                mint,
+                &sender,
+                &payer.pubkey(),
+                &[],
+                TRANSFER * HOLDERS as u64,
+            )
+            .unwrap(),
+        ],
+        &[],
+    );
+
+    let holders: Vec<Pubkey> = (0..HOLDERS).map(|_| Pubkey::new_unique()).collect();
+    for batch in holders.chunks(4) {
+        let mut ixs = Vec::new();
+        for owner in batch {
+            ixs.push(create_associated_token_account(&payer.pubkey(), owner, mint, &spl_token_2022::ID));
+            ixs.push(
+                fee_ix::transfer_checked_with_fee(
+                    &spl_token_2022::ID,
+                    &sender,
+                    mint,
+                    &ata(owner, mint),
+                    &payer.pubkey(),
+                    &[],
+                    TRANSFER,
+                    DECIMALS,
+                    FEE,
+                )
+                .unwrap(),
+            );
+        }
+        send(rpc, payer, &ixs, &[]);
+    }
+    holders.iter().map(|owner| ata(owner, mint)).collect()
+}
+
+#[test]
+#[ignore = "needs solana-test-validator with the router deployed"]
+fn crank_sweeps_fee_bearing_accounts() {
+    let url = std::env::var("DEADS_CRANK_TEST_RPC").unwrap_or_else(|_| "http://127.0.0.1:8899".to_string());
+    let rpc = RpcClient::new_with_commitment(url, CommitmentConfig::confirmed());
+    let payer = Keypair::new();
+    let airdrop = rpc.request_airdrop(&payer.pubkey(), 10_000_000_000).unwrap();
+    while !rpc.confirm_transaction(&airdrop).unwrap() {
+        std::thread::sleep(std::time::Duration::from_millis(200));
+    }
+
+    let (mint, sinks) = setup_router(&rpc, &payer);
+    let mut holders = fee_bearing_accounts(&rpc, &payer, &mint);
+
+    let accounts = scan_fee_accounts(&rpc, &mint, 1).unwrap();
+    let mut found: Vec<Pubkey> = accounts.iter().map(|acc| acc.address).collect();
+    found.sort();
+    holders.sort();
+    assert_eq!(found, holders);
+    assert!(accounts.iter().all(|acc| acc.withheld == FEE));
+
+    let crank = Crank::load(&rpc, mint, Vec::new()).unwrap();
+    crank.check_caller(&payer.pubkey()).unwrap();
+    let planned = crank.plan(&payer.pubkey(), &accounts, usize::MAX).unwrap();
+    assert!(planned.len() > 1);
+    let chunks = crank
+        .budget(&rpc, &payer.pubkey(), planned.clone(), MAX_COMPUTE_UNIT_LIMIT)
+        .unwrap();
+    assert_eq!(chunks.len(), planned.len());
+    assert!(chunks.iter().all(|chunk| chunk.compute_unit_limit < MAX_COMPUTE_UNIT_LIMIT));
+
+    // A tight cap halves chunks instead of failing them
+    let tight = chunks.iter().map(|chunk| chunk.compute_unit_limit).max().unwrap() - 1;
+    let split = crank.budget(&rpc, &payer.pubkey(), planned, tight).unwrap();
+    assert!(split.len() > chunks.len());
+    assert!(split.iter().all(|chunk| chunk.compute_unit_limit <= tight));
+
+    // Dry run: every chunk simulates, nothing moves
+    for chunk in &chunks {
+        let outcome = crank.execute(&rpc, &payer, chunk, true).unwrap();
+        assert!(matches!(outcome, Outcome::Simulated { .. }));
+    }
+    assert_eq!(scan_fee_accounts(&rpc, &mint, 1).unwrap().len(), HOLDERS);
+
+    for chunk in &chunks {
+        let outcome = crank.execute(&rpc, &payer, chunk, false).unwrap();
+        assert!(matches!(outcome, Outcome::Sent(_)));
+    }
+    // The holders are swept; only fees recycled onto the sinks remain
+    let sink_accounts: Vec<Pubkey> = crank.state.splits.iter().map(|split| split.recipient).collect();
+    let left = scan_fee_accounts(&rpc, &mint, 1).unwrap();
+    assert!(left.iter().all(|acc| sink_accounts.contains(&acc.address)));
+    assert!(balance(&rpc, &sinks.stakers_wallet) > 0);
+}
@@ -0,0 +1,345 @@
+// crates/deads-indexer/src/lib.rs
+
+//! ============================================================================
+//! deads-indexer — payout event indexer for the Solana Deads fee router
+//! ----------------------------------------------------------------------------
+//! Turns the router's transaction logs into SQLite rows finance can reconcile
+//! against token balances:
+//!   1) Attributes every `Program data:` line to the program that was running
+//!      when it was logged (the invoke stack), so events from CPIs into other
+//!      programs are never mistaken for the router's
+//!   2) Decodes the Anchor event discriminator and Borsh payload of
+//!      `HarvestRun` and `FeeDistribution`
+//!   3) Stores each event under (signature, event index), so backfills and the
+//!      live tail can overlap without duplicating rows
+//!
+//! Failed transactions are recorded without events: theirs were rolled back.
+//! ============================================================================
+
+pub mod store;
+
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use solana_rpc_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_rpc_client_api::config::RpcTransactionConfig;
+use solana_rpc_client_api::response::{Response, RpcLogsResponse};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status_client_types::option_serializer::OptionSerializer;
+use solana_transaction_status_client_types::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
+use solanadeads_fee_router::{FeeDistribution, HarvestRun};
+
+pub use store::Store;
+
+// ------------------------------ Decoding -------------------------------------
+
+const PROGRAM_DATA: &str = "data: ";
+/// Logged by the runtime in place of everything past the log limit.
+const LOG_TRUNCATED: &str = "Log truncated";
+
+pub enum RouterEvent {
+    HarvestRun(HarvestRun),
+    FeeDistribution(FeeDistribution),
+}
+
+/// A decoded event and its position among the router's `Program data:`
+/// lines in the transaction.
+pub struct LoggedEvent {
+    pub index: u32,
+    pub event: RouterEvent,
+}
+
+/// Decode one `emit!` payload: 8-byte discriminator, then the Borsh event.
+/// `Ok(None)` for router events the indexer does not store.
+pub fn decode_event(data: &[u8]) -> Result<Option<RouterEvent>> {
+    let Some((discriminator, payload)) = data.split_at_checked(8) else {
+        return Ok(None);
+    };
+    let event = if discriminator == HarvestRun::DISCRIMINATOR {
+        RouterEvent::HarvestRun(HarvestRun::try_from_slice(payload).context("decoding HarvestRun")?)
+    } else if discriminator == FeeDistribution::DISCRIMINATOR {
+        RouterEvent::FeeDistribution(FeeDistribution::try_from_slice(payload).context("decoding FeeDistribution")?)
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(event))
+}
+
+/// Events `program_id` logged itself, in log order. `Program data:` lines are
+/// attributed with the invoke stack, so the router's events count whether it
+/// ran top-level or under a CPI, and events of programs it calls do not.
+pub fn parse_logs(program_id: &Pubkey, logs: &[String]) -> Result<Vec<LoggedEvent>> {
+    let mut stack: Vec<Pubkey> = Vec::new();
+    let mut events = Vec::new();
+    let mut index = 0;
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(data) = rest.strip_prefix(PROGRAM_DATA) {
+            if stack.last() != Some(program_id) {
+                continue;
+            }
+            // Anchor logs the whole event as one field
+            let field = data.split(' ').next().unwrap_or_default();
+            let bytes = BASE64
+                .decode(field)
+                .with_context(|| format!("bad base64 in {line:?}"))?;
+            if let Some(event) = decode_event(&bytes).with_context(|| format!("event {index}"))? {
+                events.push(LoggedEvent { index, event });
+            }
+            index += 1;
+            continue;
+        }
+        // "Program <id> invoke [n]", "Program <id> success", "Program <id> failed: ..."
+        let Some((id, status)) = rest.split_once(' ') else {
+            continue;
+        };
+        let Ok(id) = id.parse::<Pubkey>() else {
+            continue;
+        };
+        if status.starts_with("invoke [") {
+            stack.push(id);
+        } else if status == "success" || status.starts_with("failed") {
+            stack.pop();
+        }
+    }
+    Ok(events)
+}
+
+// ------------------------------ Transactions ---------------------------------
+
+/// The parts of a transaction the indexer reads, from either a log
+/// subscription or `getTransaction`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoggedTx {
+    pub signature: String,
+    pub slot: u64,
+    /// Not delivered by log subscriptions.
+    pub block_time: Option<i64>,
+    pub failed: bool,
+    pub logs: Vec<String>,
+}
+
+impl LoggedTx {
+    pub fn from_confirmed(signature: &str, tx: EncodedConfirmedTransactionWithStatusMeta) -> Result<Self> {
+        let meta = tx
+            .transaction
+            .meta
+            .with_context(|| format!("transaction {signature} has no status meta"))?;
+        let logs = match meta.log_messages {
+            OptionSerializer::Some(logs) => logs,
+            _ => bail!("transaction {signature} has no log messages"),
+        };
+        Ok(Self {
+            signature: signature.to_string(),
+            slot: tx.slot,
+            block_time: tx.block_time,
+            failed: meta.err.is_some(),
+            logs,
+        })
+    }
+
+    /// Whether the runtime cut the logs short, so events may be missing.
+    pub fn truncated(&self) -> bool {
+        self.logs.iter().any(|line| line == LOG_TRUNCATED)
+    }
+}
+
+impl From<Response<RpcLogsResponse>> for LoggedTx {
+    fn from(notification: Response<RpcLogsResponse>) -> Self {
+        Self {
+            signature: notification.value.signature,
+            slot: notification.context.slot,
+            block_time: None,
+            failed: notification.value.err.is_some(),
+            logs: notification.value.logs,
+        }
+    }
+}
+
+// ------------------------------ Indexing -------------------------------------
+
+/// Decode and store one transaction. Returns the number of events stored:
+/// zero for failed or already indexed transactions.
+pub fn index_tx(store: &mut Store, program_id: &Pubkey, tx: &LoggedTx) -> Result<usize> {
+    let events = if tx.failed {
+        Vec::new()
+    } else {
+        parse_logs(program_id, &tx.logs).with_context(|| format!("transaction {}", tx.signature))?
+    };
+    Ok(if store.insert(tx, &events)? { events.len() } else { 0 })
+}
+
+/// Signatures per `getSignaturesForAddress` page (the RPC maximum).
+const PAGE: usize = 1_000;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Backfill {
+    pub transactions: usize,
+    pub events: usize,
+}
+
+/// Index the program's transactions older than `before` (default: the
+/// newest) and newer than `until` (default: the stored cursor), walking back
+/// a page of signatures at a time.
+///
+/// When `before` is not given the cursor moves to the newest signature, but
+/// only once the whole range is in: an interrupted run is simply rerun.
+pub fn backfill(
+    rpc: &RpcClient,
+    store: &mut Store,
+    program_id: &Pubkey,
+    before: Option<Signature>,
+    until: Option<Signature>,
+) -> Result<Backfill> {
+    let until = match until {
+        Some(until) => Some(until),
+        None => store.cursor()?.map(|(signature, _)| signature.parse()).transpose()?,
+    };
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(rpc.commitment()),
+        max_supported_transaction_version: Some(0),
+    };
+    let advance_cursor = before.is_none();
+    let mut newest = None;
+    let mut cursor = before;
+    let mut report = Backfill::default();
+    loop {
+        let page = rpc.get_signatures_for_address_with_config(
+            program_id,
+            GetConfirmedSignaturesForAddress2Config {
+                before: cursor,
+                until,
+                limit: Some(PAGE),
+                commitment: Some(rpc.commitment()),
+            },
+        )?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        cursor = Some(last.signature.parse()?);
+        if newest.is_none() {
+            newest = Some((page[0].signature.clone(), page[0].slot));
+        }
+        for status in &page {
+            let signature: Signature = status.signature.parse()?;
+            let tx = rpc
+                .get_transaction_with_config(&signature, config)
+                .with_context(|| format!("fetching {signature}"))?;
+            let tx = LoggedTx::from_confirmed(&status.signature, tx)?;
+            report.events += index_tx(store, program_id, &tx)?;
+            report.transactions += 1;
+        }
+        if page.len() < PAGE {
+            break;
+        }
+    }
+    if let (true, Some((signature, slot))) = (advance_cursor, newest) {
+        store.set_cursor(&signature, slot)?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Event;
+    use solanadeads_fee_router::{AppliedFee, DistributionLeg, SkipReason, SkippedSource, REWARDS_PROGRAM_ID};
+
+    fn data_line(event: &impl Event) -> String {
+        format!("Program data: {}", BASE64.encode(event.data()))
+    }
+
+    fn harvest_run(sources: u32) -> HarvestRun {
+        HarvestRun {
+            mint: Pubkey::new_unique(),
+            caller: Pubkey::new_unique(),
+            sources,
+            empty_sources: 0,
+            withheld_total: 0,
+            harvested: 0,
+            vault_before: 0,
+            withdrawn: 0,
+            distributed: 0,
+            vault_after: 0,
+            fee: AppliedFee::default(),
+            legs: Vec::new(),
+            sinks_harvested: false,
+            from_accounts: false,
+            recycled_fee: 0,
+            recycled_fees_total: 0,
+            skipped: vec![SkippedSource {
+                account: Pubkey::new_unique(),
+                reason: SkipReason::WrongMint,
+            }],
+        }
+    }
+
+    #[test]
+    fn events_are_attributed_by_invoke_stack() {
+        let router = solanadeads_fee_router::ID;
+        let distribution = FeeDistribution {
+            mint: Pubkey::new_unique(),
+            caller: Pubkey::new_unique(),
+            fee: AppliedFee::default(),
+            legs: vec![DistributionLeg {
+                recipient: Pubkey::new_unique(),
+                gross: 100,
+                fee: 1,
+                net: 99,
+            }],
+            total: 100,
+            remainder: 0,
+        };
+        let logs: Vec<String> = vec![
+            format!("Program {REWARDS_PROGRAM_ID} invoke [1]"),
+            data_line(&harvest_run(1)),
+            format!("Program {router} invoke [2]"),
+            "Program log: success".to_string(),
+            data_line(&distribution),
+            format!("Program {REWARDS_PROGRAM_ID} invoke [3]"),
+            data_line(&harvest_run(2)),
+            format!("Program {REWARDS_PROGRAM_ID} success"),
+            data_line(&solanadeads_fee_router::HarvestSinksSet {
+                router,
+                harvest_sinks: true,
+            }),
+            data_line(&harvest_run(3)),
+            format!("Program {router} success"),
+            data_line(&harvest_run(4)),
+            format!("Program {REWARDS_PROGRAM_ID} success"),
+        ];
+        let events = parse_logs(&router, &logs).unwrap();
+        assert_eq!(events.len(), 2);
+        let RouterEvent::FeeDistribution(first) = &events[0].event else {
+            panic!("expected FeeDistribution");
+        };
+        assert_eq!(events[0].index, 0);
+        assert_eq!(first.legs[0].net, 99);
+        // The HarvestSinksSet line still takes an index
+        let RouterEvent::HarvestRun(second) = &events[1].event else {
+            panic!("expected HarvestRun");
+        };
+        assert_eq!(events[1].index, 2);
+        assert_eq!(second.sources, 3);
+        assert_eq!(second.skipped[0].reason, SkipReason::WrongMint);
+    }
+
+    #[test]
+    fn undecodable_payloads_are_errors() {
+        let router = solanadeads_fee_router::ID;
+        let mut data = harvest_run(1).data();
+        data.pop();
+        let logs = vec![
+            format!("Program {router} invoke [1]"),
+            format!("Program data: {}", BASE64.encode(data)),
+        ];
+        assert!(parse_logs(&router, &logs).is_err());
+        // Too short for a discriminator: not an Anchor event
+        assert!(decode_event(&[1, 2, 3]).unwrap().is_none());
+    }
+}
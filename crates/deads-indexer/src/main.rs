@@ -0,0 +1,129 @@
+// crates/deads-indexer/src/main.rs
+
+//! `deads-indexer`: backfill the router's HarvestRun / FeeDistribution events
+//! into SQLite, then (with `tail`) keep following its logs.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use deads_indexer::{backfill, index_tx, LoggedTx, Store};
+use solana_pubsub_client::pubsub_client::PubsubClient;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+#[derive(Parser)]
+#[command(name = "deads-indexer", version, about)]
+struct Args {
+    /// RPC endpoint.
+    #[arg(long, short = 'u', env = "DEADS_INDEXER_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+
+    /// Websocket endpoint for `tail` [default: derived from --url, as the Solana CLI does].
+    #[arg(long, env = "DEADS_INDEXER_WS_URL")]
+    ws_url: Option<String>,
+
+    /// SQLite database, created if missing.
+    #[arg(long, env = "DEADS_INDEXER_DB", default_value = "deads-indexer.sqlite")]
+    db: PathBuf,
+
+    /// Router program.
+    #[arg(long, default_value_t = solanadeads_fee_router::ID)]
+    program_id: Pubkey,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Index past transactions, newest first.
+    Backfill {
+        /// Start below this signature instead of at the newest; the cursor is
+        /// left alone, for filling in older history.
+        #[arg(long)]
+        before: Option<Signature>,
+        /// Stop at this signature [default: the stored cursor].
+        #[arg(long)]
+        until: Option<Signature>,
+    },
+    /// Catch up from the cursor, then index new transactions as they confirm.
+    Tail,
+}
+
+/// `http(s)://host:port` -> `ws(s)://host:port+1`, as the Solana CLI derives it.
+fn websocket_url(rpc_url: &str) -> Result<String> {
+    let mut url = url::Url::parse(rpc_url).with_context(|| format!("bad RPC URL {rpc_url}"))?;
+    let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+    url.set_scheme(scheme)
+        .map_err(|_| anyhow::anyhow!("cannot derive a websocket URL from {rpc_url}"))?;
+    if let Some(port) = url.port() {
+        url.set_port(Some(port + 1))
+            .map_err(|_| anyhow::anyhow!("cannot derive a websocket URL from {rpc_url}"))?;
+    }
+    Ok(url.to_string())
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let rpc = RpcClient::new_with_commitment(args.url.clone(), CommitmentConfig::confirmed());
+    let mut store = Store::open(&args.db)?;
+    let program_id = args.program_id;
+
+    match args.command {
+        Command::Backfill { before, until } => {
+            let report = backfill(&rpc, &mut store, &program_id, before, until)?;
+            println!(
+                "{} transactions, {} events indexed into {}",
+                report.transactions,
+                report.events,
+                args.db.display()
+            );
+        }
+        Command::Tail => {
+            let ws_url = match args.ws_url {
+                Some(ws_url) => ws_url,
+                None => websocket_url(&args.url)?,
+            };
+            // Subscribe before catching up so nothing lands in between; the
+            // overlap is deduplicated by signature
+            let (_subscription, notifications) = PubsubClient::logs_subscribe(
+                &ws_url,
+                RpcTransactionLogsFilter::Mentions(vec![program_id.to_string()]),
+                RpcTransactionLogsConfig {
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )
+            .with_context(|| format!("subscribing to logs on {ws_url}"))?;
+
+            let report = backfill(&rpc, &mut store, &program_id, None, None)?;
+            println!("caught up: {} transactions, {} events", report.transactions, report.events);
+
+            for notification in notifications {
+                let tx = LoggedTx::from(notification);
+                let events = index_tx(&mut store, &program_id, &tx)?;
+                store.set_cursor(&tx.signature, tx.slot)?;
+                println!("{} (slot {}): {} events", tx.signature, tx.slot, events);
+            }
+            anyhow::bail!("log subscription closed");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn websocket_url_follows_the_cli() {
+        assert_eq!(websocket_url("http://127.0.0.1:8899").unwrap(), "ws://127.0.0.1:8900/");
+        assert_eq!(
+            websocket_url("https://api.devnet.solana.com").unwrap(),
+            "wss://api.devnet.solana.com/"
+        );
+    }
+}
@@ -0,0 +1,283 @@
+// crates/deads-indexer/src/store.rs
+
+//! SQLite storage. Amounts are stored as INTEGER; a value past `i64::MAX`
+//! fails the insert rather than wrapping. `maximum_fee` is decimal TEXT:
+//! mints without a fee cap set it to `u64::MAX`.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use solanadeads_fee_router::{FeeDistribution, HarvestRun};
+
+use crate::{LoggedEvent, LoggedTx, RouterEvent};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transactions (
+    signature      TEXT PRIMARY KEY,
+    slot           INTEGER NOT NULL,
+    block_time     INTEGER,
+    failed         INTEGER NOT NULL,
+    logs_truncated INTEGER NOT NULL,
+    events         INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS harvest_runs (
+    signature           TEXT NOT NULL REFERENCES transactions (signature),
+    event_index         INTEGER NOT NULL,
+    slot                INTEGER NOT NULL,
+    mint                TEXT NOT NULL,
+    caller              TEXT NOT NULL,
+    sources             INTEGER NOT NULL,
+    empty_sources       INTEGER NOT NULL,
+    withheld_total      INTEGER NOT NULL,
+    harvested           INTEGER NOT NULL,
+    vault_before        INTEGER NOT NULL,
+    withdrawn           INTEGER NOT NULL,
+    distributed         INTEGER NOT NULL,
+    vault_after         INTEGER NOT NULL,
+    epoch               INTEGER NOT NULL,
+    fee_basis_points    INTEGER NOT NULL,
+    maximum_fee         TEXT NOT NULL,
+    fee_fallback        INTEGER NOT NULL,
+    sinks_harvested     INTEGER NOT NULL,
+    from_accounts       INTEGER NOT NULL,
+    recycled_fee        INTEGER NOT NULL,
+    recycled_fees_total INTEGER NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE TABLE IF NOT EXISTS skipped_sources (
+    signature   TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    position    INTEGER NOT NULL,
+    account     TEXT NOT NULL,
+    reason      TEXT NOT NULL,
+    PRIMARY KEY (signature, event_index, position),
+    FOREIGN KEY (signature, event_index) REFERENCES harvest_runs (signature, event_index)
+);
+CREATE TABLE IF NOT EXISTS fee_distributions (
+    signature        TEXT NOT NULL REFERENCES transactions (signature),
+    event_index      INTEGER NOT NULL,
+    slot             INTEGER NOT NULL,
+    mint             TEXT NOT NULL,
+    caller           TEXT NOT NULL,
+    epoch            INTEGER NOT NULL,
+    fee_basis_points INTEGER NOT NULL,
+    maximum_fee      TEXT NOT NULL,
+    fee_fallback     INTEGER NOT NULL,
+    total            INTEGER NOT NULL,
+    remainder        INTEGER NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE TABLE IF NOT EXISTS distribution_legs (
+    signature   TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    leg         INTEGER NOT NULL,
+    recipient   TEXT NOT NULL,
+    gross       INTEGER NOT NULL,
+    fee         INTEGER NOT NULL,
+    net         INTEGER NOT NULL,
+    PRIMARY KEY (signature, event_index, leg),
+    FOREIGN KEY (signature, event_index) REFERENCES fee_distributions (signature, event_index)
+);
+-- Newest signature a completed backfill or the tail has indexed
+CREATE TABLE IF NOT EXISTS cursor (
+    id        INTEGER PRIMARY KEY CHECK (id = 0),
+    signature TEXT NOT NULL,
+    slot      INTEGER NOT NULL
+);
+";
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path).with_context(|| format!("opening {}", path.display()))?;
+        Self::init(conn)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        conn.execute_batch(SCHEMA).context("creating schema")?;
+        Ok(Self { conn })
+    }
+
+    /// For ad-hoc queries.
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Store a transaction and its events atomically. Returns false, storing
+    /// nothing, if the signature is already indexed.
+    pub fn insert(&mut self, tx: &LoggedTx, events: &[LoggedEvent]) -> Result<bool> {
+        let db = self.conn.transaction()?;
+        let inserted = db.execute(
+            "INSERT OR IGNORE INTO transactions (signature, slot, block_time, failed, logs_truncated, events)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![tx.signature, tx.slot, tx.block_time, tx.failed, tx.truncated(), events.len()],
+        )?;
+        if inserted == 0 {
+            return Ok(false);
+        }
+        for event in events {
+            match &event.event {
+                RouterEvent::HarvestRun(run) => insert_harvest_run(&db, tx, event.index, run)?,
+                RouterEvent::FeeDistribution(dist) => insert_fee_distribution(&db, tx, event.index, dist)?,
+            }
+        }
+        db.commit()?;
+        Ok(true)
+    }
+
+    pub fn cursor(&self) -> Result<Option<(String, u64)>> {
+        Ok(self
+            .conn
+            .query_row("SELECT signature, slot FROM cursor WHERE id = 0", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .optional()?)
+    }
+
+    /// Move the cursor to `signature`, unless it already points at a later slot.
+    pub fn set_cursor(&self, signature: &str, slot: u64) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO cursor (id, signature, slot) VALUES (0, ?1, ?2)
+             ON CONFLICT (id) DO UPDATE SET signature = excluded.signature, slot = excluded.slot
+             WHERE excluded.slot >= cursor.slot",
+            params![signature, slot],
+        )?;
+        Ok(())
+    }
+}
+
+/// `HarvestRun.legs` repeats the same transaction's `FeeDistribution` legs,
+/// which are stored in `distribution_legs`.
+fn insert_harvest_run(db: &Transaction, tx: &LoggedTx, index: u32, run: &HarvestRun) -> Result<()> {
+    db.execute(
+        "INSERT INTO harvest_runs (signature, event_index, slot, mint, caller, sources, empty_sources,
+             withheld_total, harvested, vault_before, withdrawn, distributed, vault_after, epoch,
+             fee_basis_points, maximum_fee, fee_fallback, sinks_harvested, from_accounts,
+             recycled_fee, recycled_fees_total)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19,
+             ?20, ?21)",
+        params![
+            tx.signature,
+            index,
+            tx.slot,
+            run.mint.to_string(),
+            run.caller.to_string(),
+            run.sources,
+            run.empty_sources,
+            run.withheld_total,
+            run.harvested,
+            run.vault_before,
+            run.withdrawn,
+            run.distributed,
+            run.vault_after,
+            run.fee.epoch,
+            run.fee.transfer_fee_basis_points,
+            run.fee.maximum_fee.to_string(),
+            run.fee.fallback,
+            run.sinks_harvested,
+            run.from_accounts,
+            run.recycled_fee,
+            run.recycled_fees_total,
+        ],
+    )?;
+    for (position, skipped) in run.skipped.iter().enumerate() {
+        db.execute(
+            "INSERT INTO skipped_sources (signature, event_index, position, account, reason)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                tx.signature,
+                index,
+                position,
+                skipped.account.to_string(),
+                format!("{:?}", skipped.reason)
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+fn insert_fee_distribution(db: &Transaction, tx: &LoggedTx, index: u32, dist: &FeeDistribution) -> Result<()> {
+    db.execute(
+        "INSERT INTO fee_distributions (signature, event_index, slot, mint, caller, epoch,
+             fee_basis_points, maximum_fee, fee_fallback, total, remainder)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            tx.signature,
+            index,
+            tx.slot,
+            dist.mint.to_string(),
+            dist.caller.to_string(),
+            dist.fee.epoch,
+            dist.fee.transfer_fee_basis_points,
+            dist.fee.maximum_fee.to_string(),
+            dist.fee.fallback,
+            dist.total,
+            dist.remainder
+        ],
+    )?;
+    for (leg, entry) in dist.legs.iter().enumerate() {
+        db.execute(
+            "INSERT INTO distribution_legs (signature, event_index, leg, recipient, gross, fee, net)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                tx.signature,
+                index,
+                leg,
+                entry.recipient.to_string(),
+                entry.gross,
+                entry.fee,
+                entry.net
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(signature: &str, slot: u64) -> LoggedTx {
+        LoggedTx {
+            signature: signature.to_string(),
+            slot,
+            block_time: None,
+            failed: false,
+            logs: vec!["Log truncated".to_string()],
+        }
+    }
+
+    #[test]
+    fn transactions_are_indexed_once() {
+        let mut store = Store::open_in_memory().unwrap();
+        assert!(store.insert(&tx("a", 5), &[]).unwrap());
+        assert!(!store.insert(&tx("a", 5), &[]).unwrap());
+        let truncated: bool = store
+            .connection()
+            .query_row("SELECT logs_truncated FROM transactions WHERE signature = 'a'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert!(truncated);
+    }
+
+    #[test]
+    fn cursor_only_moves_forward() {
+        let store = Store::open_in_memory().unwrap();
+        assert_eq!(store.cursor().unwrap(), None);
+        store.set_cursor("b", 20).unwrap();
+        store.set_cursor("a", 10).unwrap();
+        assert_eq!(store.cursor().unwrap(), Some(("b".to_string(), 20)));
+        store.set_cursor("c", 30).unwrap();
+        assert_eq!(store.cursor().unwrap(), Some(("c".to_string(), 30)));
+    }
+}
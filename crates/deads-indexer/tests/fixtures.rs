@@ -0,0 +1,190 @@
+// crates/deads-indexer/tests/fixtures.rs
+
+//! Indexes the log fixtures in `tests/fixtures/` and checks the stored rows.
+//!
+//! The fixtures follow the runtime's log format: a `logsNotification` result
+//! (`*.logs.json`) as `tail` receives it, and a base64 `getTransaction`
+//! response (`*.transaction.json`) as `backfill` does. The `Program data:`
+//! payloads are the bytes the router emitted for a three-source harvest and a
+//! 50_000 `distribute_fees` in the program-test harness; the rewards
+//! program's line in the harvest is not the router's and must be ignored.
+
+use deads_indexer::{index_tx, LoggedTx, Store};
+use solana_rpc_client_api::response::{Response, RpcLogsResponse};
+use solana_transaction_status_client_types::EncodedConfirmedTransactionWithStatusMeta;
+
+const HARVEST_SIG: &str = "2n7UY3gsoBcz34my1YpeGBJPGr6JnCk33wjrzi2Pp94atH3tEF9UAG6sz2zvor6G6VNCXmR1sGE9iXp7nptWjvPH";
+const FAILED_SIG: &str = "2nQ6k8CeQ8bXy2Xg4Ka7W3RWYjDqyq7uWCzV8R8VhKqv3XjU4B4i7m7QTrF9F5DF5nxWxK7hQ6bEatWKSyQmAYxm";
+const DISTRIBUTE_SIG: &str = "z1jfHV5HS3RA1YrmjGKi2Hi5cPGKF6xeY6mmKGBBB3tAM47Nz5EofvthYuJ8KLCfcaMKXeFYz7TTisWWd7Y53JW";
+
+const MINT: &str = "7vV7d8PLra9z98Z5BvUCnMhv59kTfdsFVmXTSCHwu3wG";
+const CALLER: &str = "2Bao7vgWPW2FpF7qvuiXNaymLpZaKxE8ZhEFK74L176f";
+const STAKERS: &str = "8tXbsNRuQ7WjjaAK1BLLgeyjTb4FiEj6ApFmtFVWa2tE";
+const TREASURY: &str = "434zhG1tD7w5Y3RhscnwJ8bH64spRfAxc28ti6rfjXR9";
+const LP: &str = "3gL6vtra1WsRL4d3uDpBkn51RVyFnBd87DZdMwc55RAw";
+
+/// (mint, caller, epoch, fee_basis_points, maximum_fee, fee_fallback) of a
+/// `fee_distributions` or `harvest_runs` row.
+type FeeColumns = (String, String, u64, u16, String, bool);
+
+fn fee_columns(store: &Store, table: &str, signature: &str) -> FeeColumns {
+    store
+        .connection()
+        .query_row(
+            &format!(
+                "SELECT mint, caller, epoch, fee_basis_points, maximum_fee, fee_fallback
+                 FROM {table} WHERE signature = ?1"
+            ),
+            [signature],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
+        )
+        .unwrap()
+}
+
+/// 1.00% fee capped at 1_000 tokens, at epoch 0 of the test harness.
+fn expected_fee_columns() -> FeeColumns {
+    (MINT.to_string(), CALLER.to_string(), 0, 100, "1000000000".to_string(), false)
+}
+
+fn fixture(name: &str) -> String {
+    let path = format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+    std::fs::read_to_string(&path).unwrap_or_else(|err| panic!("{path}: {err}"))
+}
+
+fn notification(name: &str) -> LoggedTx {
+    let response: Response<RpcLogsResponse> = serde_json::from_str(&fixture(name)).unwrap();
+    LoggedTx::from(response)
+}
+
+fn transaction(name: &str, signature: &str) -> LoggedTx {
+    let tx: EncodedConfirmedTransactionWithStatusMeta = serde_json::from_str(&fixture(name)).unwrap();
+    LoggedTx::from_confirmed(signature, tx).unwrap()
+}
+
+fn legs(store: &Store, signature: &str) -> Vec<(String, i64, i64, i64)> {
+    let mut stmt = store
+        .connection()
+        .prepare(
+            "SELECT recipient, gross, fee, net FROM distribution_legs
+             WHERE signature = ?1 ORDER BY event_index, leg",
+        )
+        .unwrap();
+    stmt.query_map([signature], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+}
+
+#[test]
+fn indexes_harvest_run_and_distribution() {
+    let mut store = Store::open_in_memory().unwrap();
+    let tx = notification("harvest_and_distribute.logs.json");
+    assert_eq!(tx.slot, 318_204_117);
+    assert_eq!(index_tx(&mut store, &solanadeads_fee_router::ID, &tx).unwrap(), 2);
+
+    assert_eq!(
+        legs(&store, HARVEST_SIG),
+        vec![
+            (STAKERS.to_string(), 19_500, 195, 19_305),
+            (TREASURY.to_string(), 5_250, 53, 5_197),
+            (LP.to_string(), 5_250, 53, 5_197),
+        ]
+    );
+    let (index, total, remainder): (u32, i64, i64) = store
+        .connection()
+        .query_row(
+            "SELECT event_index, total, remainder FROM fee_distributions WHERE signature = ?1",
+            [HARVEST_SIG],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .unwrap();
+    assert_eq!((index, total, remainder), (0, 30_000, 0));
+
+    // FeeDistribution is logged before the rewards CPI, HarvestRun after it
+    let run: (u32, u64, u32, i64, i64, i64, i64, i64, i64) = store
+        .connection()
+        .query_row(
+            "SELECT event_index, slot, sources, withheld_total, harvested, withdrawn, distributed,
+                    vault_after, recycled_fee
+             FROM harvest_runs WHERE signature = ?1",
+            [HARVEST_SIG],
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                    row.get(6)?,
+                    row.get(7)?,
+                    row.get(8)?,
+                ))
+            },
+        )
+        .unwrap();
+    assert_eq!(run, (1, 318_204_117, 3, 30_000, 30_000, 30_000, 30_000, 0, 301));
+    assert_eq!(fee_columns(&store, "harvest_runs", HARVEST_SIG), expected_fee_columns());
+    assert_eq!(fee_columns(&store, "fee_distributions", HARVEST_SIG), expected_fee_columns());
+
+    // Delivered again by a backfill: nothing is duplicated
+    assert_eq!(index_tx(&mut store, &solanadeads_fee_router::ID, &tx).unwrap(), 0);
+    let rows: i64 = store
+        .connection()
+        .query_row("SELECT COUNT(*) FROM distribution_legs", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(rows, 3);
+}
+
+#[test]
+fn failed_transactions_store_no_events() {
+    let mut store = Store::open_in_memory().unwrap();
+    // The FeeDistribution logged before the rewards CPI failed was rolled back
+    let tx = notification("failed_harvest.logs.json");
+    assert!(tx.failed);
+    assert_eq!(index_tx(&mut store, &solanadeads_fee_router::ID, &tx).unwrap(), 0);
+
+    let (failed, events): (bool, i64) = store
+        .connection()
+        .query_row(
+            "SELECT failed, events FROM transactions WHERE signature = ?1",
+            [FAILED_SIG],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    assert!(failed);
+    assert_eq!(events, 0);
+    assert!(legs(&store, FAILED_SIG).is_empty());
+}
+
+#[test]
+fn indexes_backfilled_transaction() {
+    let mut store = Store::open_in_memory().unwrap();
+    let tx = transaction("distribute_fees.transaction.json", DISTRIBUTE_SIG);
+    assert_eq!((tx.slot, tx.block_time, tx.failed), (318_210_342, Some(1_792_231_457), false));
+    assert_eq!(index_tx(&mut store, &solanadeads_fee_router::ID, &tx).unwrap(), 1);
+
+    assert_eq!(
+        legs(&store, DISTRIBUTE_SIG),
+        vec![
+            (STAKERS.to_string(), 32_500, 325, 32_175),
+            (TREASURY.to_string(), 8_750, 88, 8_662),
+            (LP.to_string(), 8_750, 88, 8_662),
+        ]
+    );
+    let block_time: Option<i64> = store
+        .connection()
+        .query_row(
+            "SELECT block_time FROM transactions WHERE signature = ?1",
+            [DISTRIBUTE_SIG],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(block_time, Some(1_792_231_457));
+    assert_eq!(fee_columns(&store, "fee_distributions", DISTRIBUTE_SIG), expected_fee_columns());
+    let runs: i64 = store
+        .connection()
+        .query_row("SELECT COUNT(*) FROM harvest_runs", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(runs, 0);
+}
@@ -0,0 +1,119 @@
+// crates/router-cli/src/config.rs
+
+//! Cluster and keypair resolution, like the Solana CLI: explicit flags win,
+//! then the CLI config file (`~/.config/solana/cli/config.yml` unless
+//! `--config` says otherwise), then the CLI's own defaults.
+//!
+//! The YAML is read directly rather than through `solana-cli-config`, which
+//! pulls in hardware-wallet support and a system libudev.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+const DEFAULT_URL: &str = "https://api.mainnet-beta.solana.com";
+
+/// The fields of the Solana CLI config this tool uses.
+#[derive(Debug, Default, Deserialize)]
+struct CliConfig {
+    json_rpc_url: Option<String>,
+    keypair_path: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Settings {
+    pub url: String,
+    pub keypair_path: String,
+}
+
+fn solana_dir() -> PathBuf {
+    PathBuf::from(std::env::var_os("HOME").unwrap_or_default()).join(".config/solana")
+}
+
+fn load(path: &str) -> Result<CliConfig> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("loading config {path}"))?;
+    serde_yaml::from_str(&text).with_context(|| format!("parsing config {path}"))
+}
+
+pub fn resolve(config_file: Option<&str>, url: Option<&str>, keypair: Option<&str>) -> Result<Settings> {
+    let config = match config_file {
+        Some(path) => load(path)?,
+        None => {
+            let path = solana_dir().join("cli/config.yml");
+            match path.to_str() {
+                Some(path) if std::path::Path::new(path).exists() => load(path)?,
+                _ => CliConfig::default(),
+            }
+        }
+    };
+    let url = url
+        .map(str::to_string)
+        .or(config.json_rpc_url)
+        .unwrap_or_else(|| DEFAULT_URL.to_string());
+    let keypair_path = keypair
+        .map(str::to_string)
+        .or(config.keypair_path)
+        .unwrap_or_else(|| solana_dir().join("id.json").to_string_lossy().into_owned());
+    Ok(Settings {
+        url: normalize_url(&url),
+        keypair_path,
+    })
+}
+
+/// Expand the Solana CLI cluster monikers; anything else is taken as a URL.
+pub fn normalize_url(url: &str) -> String {
+    match url {
+        "m" | "mainnet-beta" => "https://api.mainnet-beta.solana.com",
+        "d" | "devnet" => "https://api.devnet.solana.com",
+        "t" | "testnet" => "https://api.testnet.solana.com",
+        "l" | "localhost" => "http://localhost:8899",
+        other => other,
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_override_config_file() {
+        let dir = std::env::temp_dir().join(format!("router-cli-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.yml");
+        // As written by `solana config set`
+        std::fs::write(
+            &path,
+            "---\njson_rpc_url: \"https://api.devnet.solana.com\"\nwebsocket_url: \"\"\n\
+             keypair_path: /keys/ops.json\naddress_labels:\n  \"11111111111111111111111111111111\": System Program\n\
+             commitment: confirmed\n",
+        )
+        .unwrap();
+        let path = path.to_str().unwrap();
+
+        assert_eq!(
+            resolve(Some(path), None, None).unwrap(),
+            Settings {
+                url: "https://api.devnet.solana.com".to_string(),
+                keypair_path: "/keys/ops.json".to_string(),
+            }
+        );
+        assert_eq!(
+            resolve(Some(path), Some("l"), Some("/keys/other.json")).unwrap(),
+            Settings {
+                url: "http://localhost:8899".to_string(),
+                keypair_path: "/keys/other.json".to_string(),
+            }
+        );
+        assert!(resolve(Some(dir.join("missing.yml").to_str().unwrap()), None, None).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn monikers_expand() {
+        assert_eq!(normalize_url("devnet"), "https://api.devnet.solana.com");
+        assert_eq!(normalize_url("m"), "https://api.mainnet-beta.solana.com");
+        assert_eq!(normalize_url("http://10.0.0.1:8899"), "http://10.0.0.1:8899");
+    }
+}
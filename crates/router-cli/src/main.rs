@@ -0,0 +1,403 @@
+// crates/router-cli/src/main.rs
+
+//! `router-cli`: administer and inspect a fee router from the command line.
+//! Cluster and signer come from the Solana CLI config unless overridden;
+//! every command prints human-readable text, or JSON with `--output json`.
+
+mod config;
+mod view;
+
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use anchor_lang::AccountDeserialize;
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig};
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use solanadeads_fee_router::DEADS_MINT;
+use solanadeads_fee_router_client::{self as client, Router, SinkConfig, MAX_COMPUTE_UNIT_LIMIT};
+use spl_token_2022::extension::StateWithExtensions;
+use spl_token_2022::state::Mint as SplMint;
+use view::{BalanceChange, MintView, RouterView, SentView, ShowView, SimulationView, VaultView};
+
+#[derive(Parser)]
+#[command(name = "router-cli", version, about)]
+struct Cli {
+    /// Solana CLI config file [default: ~/.config/solana/cli/config.yml].
+    #[arg(long, short = 'C', global = true)]
+    config: Option<String>,
+
+    /// RPC URL or moniker (mainnet-beta, devnet, testnet, localhost).
+    #[arg(long, short = 'u', global = true)]
+    url: Option<String>,
+
+    /// Signer keypair file (fee payer, authority or crank caller).
+    #[arg(long, short = 'k', global = true)]
+    keypair: Option<String>,
+
+    /// Router mint.
+    #[arg(long, global = true, default_value = DEADS_MINT)]
+    mint: Pubkey,
+
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Display)]
+    output: OutputFormat,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Display,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the router for the mint with the default split table; the
+    /// signer becomes its authority.
+    Init {
+        #[arg(long)]
+        treasury_owner: Pubkey,
+        #[arg(long)]
+        lp_owner: Pubkey,
+        /// Rewards vault token account [default: the default rewards program's vault].
+        #[arg(long)]
+        stakers_wallet: Option<Pubkey>,
+    },
+    /// Decode the router, its vault balance and the mint's transfer fee config.
+    Show,
+    /// Pay AMOUNT (base units) out of the vault per the split table.
+    Distribute { amount: u64 },
+    /// Harvest the token accounts listed in a file, then distribute.
+    Harvest {
+        /// One account per line; blank lines and `#` comments are ignored.
+        #[arg(long)]
+        from_file: PathBuf,
+        /// Pool ids forwarded to the rewards sync (comma-separated).
+        #[arg(long, value_delimiter = ',')]
+        pool_ids: Vec<u8>,
+        /// Cap on sources per transaction (the packet size caps it too).
+        #[arg(long, default_value_t = 64)]
+        max_accounts_per_tx: usize,
+        /// Cap on each transaction's compute-unit limit; chunks that need more are split.
+        #[arg(long, default_value_t = MAX_COMPUTE_UNIT_LIMIT)]
+        max_compute_units: u32,
+    },
+    /// Simulate a harvest (or `distribute_fees` with --distribute) and show
+    /// the vault and sink balance changes, without sending anything.
+    Simulate {
+        /// Harvest sources, as for `harvest`; without it only the mint's
+        /// withheld fees and the vault are distributed.
+        #[arg(long)]
+        from_file: Option<PathBuf>,
+        /// Simulate `distribute_fees` of this amount instead.
+        #[arg(long, conflicts_with = "from_file")]
+        distribute: Option<u64>,
+        #[arg(long, value_delimiter = ',')]
+        pool_ids: Vec<u8>,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let settings = config::resolve(cli.config.as_deref(), cli.url.as_deref(), cli.keypair.as_deref())?;
+    let rpc = RpcClient::new_with_commitment(settings.url, CommitmentConfig::confirmed());
+    let signer = || {
+        read_keypair_file(&settings.keypair_path)
+            .map_err(|err| anyhow!("reading keypair {}: {err}", settings.keypair_path))
+    };
+    let mint = cli.mint;
+
+    match cli.command {
+        Command::Init {
+            treasury_owner,
+            lp_owner,
+            stakers_wallet,
+        } => {
+            let payer = signer()?;
+            let mut sinks: SinkConfig = client::default_sinks(treasury_owner, lp_owner, &mint);
+            if let Some(stakers_wallet) = stakers_wallet {
+                sinks.stakers_wallet = stakers_wallet;
+            }
+            let ix = client::initialize_router(&payer.pubkey(), &mint, sinks);
+            let signature = send(&rpc, &payer, &[ix])?;
+            print(cli.output, &SentView {
+                command: "init",
+                signatures: vec![signature],
+            })
+        }
+        Command::Show => print(cli.output, &show(&rpc, &mint)?),
+        Command::Distribute { amount } => {
+            let payer = signer()?;
+            let (_, router) = fetch_router(&rpc, &mint)?;
+            let decimals = mint_decimals(&rpc, &mint)?;
+            let ix = client::distribute_fees(&payer.pubkey(), &mint, &router, amount, decimals);
+            let signature = send(&rpc, &payer, &[ix])?;
+            print(cli.output, &SentView {
+                command: "distribute",
+                signatures: vec![signature],
+            })
+        }
+        Command::Harvest {
+            from_file,
+            pool_ids,
+            max_accounts_per_tx,
+            max_compute_units,
+        } => {
+            let payer = signer()?;
+            let (_, router) = fetch_router(&rpc, &mint)?;
+            let sources = read_sources(&from_file)?;
+            let harvest = |units: u32, chunk: &[Pubkey]| {
+                with_compute_limit(
+                    units,
+                    client::harvest_and_distribute(&payer.pubkey(), &mint, &router, chunk, pool_ids.clone()),
+                )
+            };
+            let chunks = client::chunk_by(&sources, max_accounts_per_tx, |chunk| {
+                client::tx_size(&payer.pubkey(), &harvest(MAX_COMPUTE_UNIT_LIMIT, chunk)) <= PACKET_DATA_SIZE
+            })?;
+            let chunks = client::fit_compute(chunks, max_compute_units, |chunk| {
+                client::simulate_units(&rpc, &payer.pubkey(), &harvest(MAX_COMPUTE_UNIT_LIMIT, chunk))
+            })?;
+            let mut signatures = Vec::with_capacity(chunks.len());
+            for (chunk, units) in &chunks {
+                signatures.push(send(&rpc, &payer, &harvest(*units, chunk))?);
+            }
+            print(cli.output, &SentView {
+                command: "harvest",
+                signatures,
+            })
+        }
+        Command::Simulate {
+            from_file,
+            distribute,
+            pool_ids,
+        } => {
+            let payer = signer()?;
+            let (_, router) = fetch_router(&rpc, &mint)?;
+            let (name, ix) = match distribute {
+                Some(amount) => {
+                    let decimals = mint_decimals(&rpc, &mint)?;
+                    (
+                        "distribute_fees",
+                        client::distribute_fees(&payer.pubkey(), &mint, &router, amount, decimals),
+                    )
+                }
+                None => {
+                    let sources = match from_file {
+                        Some(path) => read_sources(&path)?,
+                        None => Vec::new(),
+                    };
+                    (
+                        "harvest_and_distribute",
+                        client::harvest_and_distribute(&payer.pubkey(), &mint, &router, &sources, pool_ids),
+                    )
+                }
+            };
+            let ixs = with_compute_limit(MAX_COMPUTE_UNIT_LIMIT, ix.clone());
+            if client::tx_size(&payer.pubkey(), &ixs) > PACKET_DATA_SIZE {
+                bail!("too many sources for one transaction; simulate a shorter list");
+            }
+            print(cli.output, &simulate(&rpc, &payer, &mint, &router, name, ix)?)
+        }
+    }
+}
+
+fn print<T: Serialize + Display>(format: OutputFormat, value: &T) -> Result<()> {
+    match format {
+        OutputFormat::Display => print!("{value}"),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+    }
+    Ok(())
+}
+
+fn send(rpc: &RpcClient, payer: &Keypair, ixs: &[Instruction]) -> Result<String> {
+    let blockhash = rpc.get_latest_blockhash()?;
+    let tx = Transaction::new_signed_with_payer(ixs, Some(&payer.pubkey()), &[payer], blockhash);
+    Ok(rpc.send_and_confirm_transaction(&tx)?.to_string())
+}
+
+/// `set_compute_unit_limit(units)` followed by `ix`.
+fn with_compute_limit(units: u32, ix: Instruction) -> Vec<Instruction> {
+    vec![ComputeBudgetInstruction::set_compute_unit_limit(units), ix]
+}
+
+
+fn fetch_router(rpc: &RpcClient, mint: &Pubkey) -> Result<(Pubkey, Router)> {
+    let address = client::router_pda(mint);
+    let data = rpc
+        .get_account_data(&address)
+        .with_context(|| format!("router {address} for mint {mint}"))?;
+    let router = Router::try_deserialize(&mut data.as_slice()).context("decoding router")?;
+    Ok((address, router))
+}
+
+fn mint_decimals(rpc: &RpcClient, mint: &Pubkey) -> Result<u8> {
+    let data = rpc.get_account_data(mint).with_context(|| format!("mint {mint}"))?;
+    Ok(StateWithExtensions::<SplMint>::unpack(&data)
+        .context("not a Token-2022 mint")?
+        .base
+        .decimals)
+}
+
+fn show(rpc: &RpcClient, mint: &Pubkey) -> Result<ShowView> {
+    let (address, router) = fetch_router(rpc, mint)?;
+    let vault = client::router_vault(mint);
+    let epoch = rpc.get_epoch_info()?.epoch;
+    let mint_data = rpc.get_account_data(mint).with_context(|| format!("mint {mint}"))?;
+    // The vault ATA may not exist yet on a fresh router
+    let balance = match rpc.get_account_with_commitment(&vault, rpc.commitment())?.value {
+        Some(account) => view::token_balance(&account.data)?,
+        None => 0,
+    };
+    Ok(ShowView {
+        router: RouterView::new(&address, &router),
+        vault: VaultView {
+            address: vault.to_string(),
+            balance,
+        },
+        mint: MintView::decode(mint, &mint_data, epoch)?,
+    })
+}
+
+/// Simulate `ix` and report token balance changes on the vault and sinks.
+fn simulate(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    mint: &Pubkey,
+    router: &Router,
+    name: &'static str,
+    ix: Instruction,
+) -> Result<SimulationView> {
+    let mut watch = vec![("vault".to_string(), client::router_vault(mint))];
+    watch.extend(router.splits.iter().enumerate().map(|(i, split)| {
+        let label = if split.recipient == router.stakers_wallet {
+            "stakers".to_string()
+        } else {
+            format!("sink {i}")
+        };
+        (label, split.recipient)
+    }));
+    let addresses: Vec<Pubkey> = watch.iter().map(|(_, address)| *address).collect();
+    let before = rpc.get_multiple_accounts(&addresses)?;
+
+    // Under the maximum limit, so a large harvest is not cut off at the
+    // default budget and `units_consumed` is what it really needs
+    let ixs = with_compute_limit(MAX_COMPUTE_UNIT_LIMIT, ix);
+    let blockhash = rpc.get_latest_blockhash()?;
+    let tx = Transaction::new_signed_with_payer(&ixs, Some(&payer.pubkey()), &[payer], blockhash);
+    let result = rpc
+        .simulate_transaction_with_config(
+            &tx,
+            RpcSimulateTransactionConfig {
+                replace_recent_blockhash: true,
+                accounts: Some(RpcSimulateTransactionAccountsConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    addresses: addresses.iter().map(Pubkey::to_string).collect(),
+                }),
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )?
+        .value;
+
+    let after = result.accounts.unwrap_or_default();
+    let balance = |account: Option<Account>| account.map_or(Ok(0), |account| view::token_balance(&account.data));
+    let mut balances = Vec::with_capacity(watch.len());
+    for (i, (label, address)) in watch.into_iter().enumerate() {
+        let before = balance(before[i].clone())?;
+        let after = match after.get(i).cloned().flatten() {
+            Some(ui) => balance(ui.decode::<Account>())?,
+            None => before,
+        };
+        balances.push(BalanceChange {
+            label,
+            account: address.to_string(),
+            before,
+            after,
+        });
+    }
+
+    Ok(SimulationView {
+        instruction: name,
+        ok: result.err.is_none(),
+        err: result.err.map(|err| err.to_string()),
+        units_consumed: result.units_consumed,
+        balances,
+        logs: result.logs.unwrap_or_default(),
+    })
+}
+
+fn read_sources(path: &Path) -> Result<Vec<Pubkey>> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    parse_sources(&text).with_context(|| format!("in {}", path.display()))
+}
+
+/// One account per line, `#` starts a comment; duplicates are dropped.
+fn parse_sources(text: &str) -> Result<Vec<Pubkey>> {
+    let mut seen = HashSet::new();
+    let mut sources = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let entry = line.split('#').next().unwrap_or_default().trim();
+        if entry.is_empty() {
+            continue;
+        }
+        let source: Pubkey = entry
+            .parse()
+            .map_err(|err| anyhow!("line {}: {entry:?}: {err}", i + 1))?;
+        if seen.insert(source) {
+            sources.push(source);
+        }
+    }
+    Ok(sources)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn cli_is_well_formed() {
+        Cli::command().debug_assert();
+        let cli = Cli::try_parse_from(["router-cli", "--output", "json", "simulate", "--distribute", "5"]).unwrap();
+        assert!(matches!(cli.output, OutputFormat::Json));
+        assert!(matches!(cli.command, Command::Simulate { distribute: Some(5), .. }));
+        assert!(Cli::try_parse_from(["router-cli", "simulate", "--distribute", "5", "--from-file", "x"]).is_err());
+    }
+
+    #[test]
+    fn harvest_takes_a_compute_unit_cap() {
+        let cli = Cli::try_parse_from(["router-cli", "harvest", "--from-file", "x"]).unwrap();
+        assert!(matches!(cli.command, Command::Harvest { max_compute_units: MAX_COMPUTE_UNIT_LIMIT, .. }));
+        let args = ["router-cli", "harvest", "--from-file", "x", "--max-compute-units", "600000"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert!(matches!(cli.command, Command::Harvest { max_compute_units: 600_000, .. }));
+
+        let ix = Instruction::new_with_bytes(client::PROGRAM_ID, &[], Vec::new());
+        let ixs = with_compute_limit(300_000, ix.clone());
+        assert_eq!(ixs, vec![ComputeBudgetInstruction::set_compute_unit_limit(300_000), ix]);
+    }
+
+    #[test]
+    fn parses_source_files() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let text = format!("# holders\n{a}\n\n  {b}  # second\n{a}\n");
+        assert_eq!(parse_sources(&text).unwrap(), vec![a, b]);
+
+        let err = parse_sources(&format!("{a}\nnot-a-key\n")).unwrap_err();
+        assert!(err.to_string().starts_with("line 2"));
+    }
+}
@@ -0,0 +1,420 @@
+// crates/router-cli/src/view.rs
+
+//! Serializable views of on-chain state and command results. Each one
+//! prints as JSON with `--output json` and through `Display` otherwise.
+
+use std::fmt;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use solanadeads_fee_router_client::{Router, RouterStats};
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use spl_token_2022::state::{Account as SplAccount, Mint as SplMint};
+
+fn key(pubkey: &Pubkey) -> String {
+    pubkey.to_string()
+}
+
+/// `None` for the all-zero "unset" key.
+fn optional_key(pubkey: &Pubkey) -> Option<String> {
+    (*pubkey != Pubkey::default()).then(|| key(pubkey))
+}
+
+fn or_none(value: &Option<String>) -> &str {
+    value.as_deref().unwrap_or("none")
+}
+
+// ------------------------------ show -----------------------------------------
+
+#[derive(Serialize)]
+pub struct SplitView {
+    pub recipient: String,
+    pub bps: u16,
+}
+
+#[derive(Serialize)]
+pub struct SinkTotalView {
+    pub recipient: String,
+    pub gross: u64,
+    pub net: u64,
+}
+
+#[derive(Serialize)]
+pub struct StatsView {
+    pub total_harvested: u64,
+    pub total_withdrawn: u64,
+    pub total_distributed: u64,
+    pub harvest_runs: u64,
+    pub last_run_slot: u64,
+    pub last_run_timestamp: i64,
+    pub sinks: Vec<SinkTotalView>,
+    pub retired_gross: u64,
+    pub retired_net: u64,
+}
+
+impl From<&RouterStats> for StatsView {
+    fn from(stats: &RouterStats) -> Self {
+        Self {
+            total_harvested: stats.total_harvested,
+            total_withdrawn: stats.total_withdrawn,
+            total_distributed: stats.total_distributed,
+            harvest_runs: stats.harvest_runs,
+            last_run_slot: stats.last_run_slot,
+            last_run_timestamp: stats.last_run_timestamp,
+            sinks: stats
+                .sinks
+                .iter()
+                .map(|sink| SinkTotalView {
+                    recipient: key(&sink.recipient),
+                    gross: sink.gross,
+                    net: sink.net,
+                })
+                .collect(),
+            retired_gross: stats.retired.gross,
+            retired_net: stats.retired.net,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct RewardsCpiView {
+    pub enabled: bool,
+    pub program_id: String,
+    pub discriminator: [u8; 8],
+}
+
+#[derive(Serialize)]
+pub struct RouterView {
+    pub address: String,
+    pub version: u8,
+    /// `None` once renounced.
+    pub authority: Option<String>,
+    pub pending_authority: Option<String>,
+    pub guardian: Option<String>,
+    pub paused: bool,
+    pub permissionless: bool,
+    pub crank_operators: Vec<String>,
+    pub treasury_owner: String,
+    pub lp_owner: String,
+    pub stakers_wallet: String,
+    pub rewards_cpi: RewardsCpiView,
+    pub harvest_sinks: bool,
+    pub lenient_sources: bool,
+    pub recycled_fees: u64,
+    pub splits: Vec<SplitView>,
+    pub stats: StatsView,
+}
+
+impl RouterView {
+    pub fn new(address: &Pubkey, router: &Router) -> Self {
+        Self {
+            address: key(address),
+            version: router.version,
+            authority: optional_key(&router.authority),
+            pending_authority: router.pending_authority.as_ref().map(key),
+            guardian: optional_key(&router.guardian),
+            paused: router.paused,
+            permissionless: router.permissionless,
+            crank_operators: router.crank_operators.iter().map(key).collect(),
+            treasury_owner: key(&router.treasury_owner),
+            lp_owner: key(&router.lp_owner),
+            stakers_wallet: key(&router.stakers_wallet),
+            rewards_cpi: RewardsCpiView {
+                enabled: router.rewards_cpi.enabled,
+                program_id: key(&router.rewards_cpi.program_id),
+                discriminator: router.rewards_cpi.discriminator,
+            },
+            harvest_sinks: router.harvest_sinks,
+            lenient_sources: router.lenient_sources,
+            recycled_fees: router.recycled_fees,
+            splits: router
+                .splits
+                .iter()
+                .map(|split| SplitView {
+                    recipient: key(&split.recipient),
+                    bps: split.bps,
+                })
+                .collect(),
+            stats: StatsView::from(&router.stats),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct EpochFeeView {
+    pub epoch: u64,
+    pub transfer_fee_basis_points: u16,
+    pub maximum_fee: u64,
+}
+
+#[derive(Serialize)]
+pub struct MintView {
+    pub address: String,
+    pub decimals: u8,
+    pub supply: u64,
+    pub transfer_fee_config_authority: Option<String>,
+    pub withdraw_withheld_authority: Option<String>,
+    /// Fees harvested to the mint and not yet withdrawn.
+    pub withheld_amount: u64,
+    pub current_fee: EpochFeeView,
+    pub next_fee: EpochFeeView,
+}
+
+impl MintView {
+    /// Decode a Token-2022 mint with a TransferFeeConfig, reading the fee in
+    /// effect at `epoch` and at the next epoch.
+    pub fn decode(address: &Pubkey, data: &[u8], epoch: u64) -> Result<Self> {
+        let state = StateWithExtensions::<SplMint>::unpack(data).context("not a Token-2022 mint")?;
+        let cfg = state
+            .get_extension::<TransferFeeConfig>()
+            .context("mint has no TransferFeeConfig")?;
+        let fee_at = |epoch: u64| {
+            let fee = cfg.get_epoch_fee(epoch);
+            EpochFeeView {
+                epoch,
+                transfer_fee_basis_points: fee.transfer_fee_basis_points.into(),
+                maximum_fee: fee.maximum_fee.into(),
+            }
+        };
+        Ok(Self {
+            address: key(address),
+            decimals: state.base.decimals,
+            supply: state.base.supply,
+            transfer_fee_config_authority: Option::<Pubkey>::from(cfg.transfer_fee_config_authority)
+                .as_ref()
+                .map(key),
+            withdraw_withheld_authority: Option::<Pubkey>::from(cfg.withdraw_withheld_authority)
+                .as_ref()
+                .map(key),
+            withheld_amount: cfg.withheld_amount.into(),
+            current_fee: fee_at(epoch),
+            next_fee: fee_at(epoch + 1),
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct VaultView {
+    pub address: String,
+    pub balance: u64,
+}
+
+#[derive(Serialize)]
+pub struct ShowView {
+    pub router: RouterView,
+    pub vault: VaultView,
+    pub mint: MintView,
+}
+
+/// Token amount held in a Token-2022 account.
+pub fn token_balance(data: &[u8]) -> Result<u64> {
+    Ok(StateWithExtensions::<SplAccount>::unpack(data)
+        .context("not a Token-2022 token account")?
+        .base
+        .amount)
+}
+
+impl fmt::Display for ShowView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let r = &self.router;
+        writeln!(f, "Router:              {} (v{})", r.address, r.version)?;
+        writeln!(f, "  authority:         {}", r.authority.as_deref().unwrap_or("renounced"))?;
+        writeln!(f, "  pending authority: {}", or_none(&r.pending_authority))?;
+        writeln!(f, "  guardian:          {}", or_none(&r.guardian))?;
+        writeln!(f, "  paused:            {}", r.paused)?;
+        writeln!(f, "  permissionless:    {}", r.permissionless)?;
+        writeln!(f, "  crank operators:   {}", r.crank_operators.join(", "))?;
+        writeln!(f, "  harvest sinks:     {}", r.harvest_sinks)?;
+        writeln!(f, "  lenient sources:   {}", r.lenient_sources)?;
+        writeln!(
+            f,
+            "  rewards CPI:       {} ({})",
+            r.rewards_cpi.program_id,
+            if r.rewards_cpi.enabled { "enabled" } else { "disabled" }
+        )?;
+        writeln!(f, "  treasury owner:    {}", r.treasury_owner)?;
+        writeln!(f, "  LP owner:          {}", r.lp_owner)?;
+        writeln!(f, "  stakers wallet:    {}", r.stakers_wallet)?;
+        writeln!(f, "  splits:")?;
+        for split in &r.splits {
+            writeln!(f, "    {:>6.2}%  {}", f64::from(split.bps) / 100.0, split.recipient)?;
+        }
+        let s = &r.stats;
+        writeln!(f, "  stats:")?;
+        writeln!(f, "    harvested:       {}", s.total_harvested)?;
+        writeln!(f, "    withdrawn:       {}", s.total_withdrawn)?;
+        writeln!(f, "    distributed:     {}", s.total_distributed)?;
+        writeln!(f, "    recycled fees:   {}", r.recycled_fees)?;
+        writeln!(
+            f,
+            "    harvest runs:    {} (last at slot {}, unix {})",
+            s.harvest_runs, s.last_run_slot, s.last_run_timestamp
+        )?;
+        for sink in &s.sinks {
+            writeln!(f, "    {}  gross {}  net {}", sink.recipient, sink.gross, sink.net)?;
+        }
+        if s.retired_gross > 0 {
+            writeln!(f, "    retired sinks    gross {}  net {}", s.retired_gross, s.retired_net)?;
+        }
+        writeln!(f, "Vault:               {}", self.vault.address)?;
+        writeln!(f, "  balance:           {}", self.vault.balance)?;
+        let m = &self.mint;
+        writeln!(f, "Mint:                {}", m.address)?;
+        writeln!(f, "  decimals:          {}", m.decimals)?;
+        writeln!(f, "  supply:            {}", m.supply)?;
+        writeln!(f, "  fee authority:     {}", or_none(&m.transfer_fee_config_authority))?;
+        writeln!(f, "  withdraw authority: {}", or_none(&m.withdraw_withheld_authority))?;
+        writeln!(f, "  withheld on mint:  {}", m.withheld_amount)?;
+        for (label, fee) in [("current", &m.current_fee), ("next", &m.next_fee)] {
+            writeln!(
+                f,
+                "  {label:<7} fee:       {} bps, max {} (epoch {})",
+                fee.transfer_fee_basis_points, fee.maximum_fee, fee.epoch
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// ------------------------------ transactions ---------------------------------
+
+#[derive(Serialize)]
+pub struct SentView {
+    pub command: &'static str,
+    pub signatures: Vec<String>,
+}
+
+impl fmt::Display for SentView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for signature in &self.signatures {
+            writeln!(f, "Signature: {signature}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+pub struct BalanceChange {
+    pub label: String,
+    pub account: String,
+    pub before: u64,
+    pub after: u64,
+}
+
+#[derive(Serialize)]
+pub struct SimulationView {
+    pub instruction: &'static str,
+    pub ok: bool,
+    pub err: Option<String>,
+    pub units_consumed: Option<u64>,
+    pub balances: Vec<BalanceChange>,
+    pub logs: Vec<String>,
+}
+
+impl fmt::Display for SimulationView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.err {
+            None => writeln!(f, "Simulated {}: ok", self.instruction)?,
+            Some(err) => writeln!(f, "Simulated {}: failed: {err}", self.instruction)?,
+        }
+        if let Some(units) = self.units_consumed {
+            writeln!(f, "  compute units: {units}")?;
+        }
+        for change in &self.balances {
+            let delta = i128::from(change.after) - i128::from(change.before);
+            writeln!(
+                f,
+                "  {:<9} {}  {} -> {} ({delta:+})",
+                change.label, change.account, change.before, change.after
+            )?;
+        }
+        if !self.ok {
+            for line in &self.logs {
+                writeln!(f, "  | {line}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spl_token_2022::extension::transfer_fee::TransferFee;
+    use spl_token_2022::extension::{BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut};
+
+    /// Fee-config mint whose fee changes from 100 to 250 bps at epoch 8.
+    fn mint_data(withdraw_authority: Pubkey) -> Vec<u8> {
+        let len = ExtensionType::try_calculate_account_len::<SplMint>(&[ExtensionType::TransferFeeConfig]).unwrap();
+        let mut data = vec![0; len];
+        let mut state = StateWithExtensionsMut::<SplMint>::unpack_uninitialized(&mut data).unwrap();
+        let cfg = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        cfg.withdraw_withheld_authority = Some(withdraw_authority).try_into().unwrap();
+        cfg.withheld_amount = 42.into();
+        cfg.older_transfer_fee = TransferFee {
+            epoch: 0.into(),
+            maximum_fee: 1_000.into(),
+            transfer_fee_basis_points: 100.into(),
+        };
+        cfg.newer_transfer_fee = TransferFee {
+            epoch: 8.into(),
+            maximum_fee: 5_000.into(),
+            transfer_fee_basis_points: 250.into(),
+        };
+        state.base = SplMint {
+            decimals: 6,
+            supply: 1_000_000,
+            is_initialized: true,
+            ..Default::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+        data
+    }
+
+    #[test]
+    fn mint_view_reads_current_and_next_fee() {
+        let (address, router) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let data = mint_data(router);
+
+        let view = MintView::decode(&address, &data, 7).unwrap();
+        assert_eq!(view.withdraw_withheld_authority, Some(router.to_string()));
+        assert_eq!(view.transfer_fee_config_authority, None);
+        assert_eq!(view.withheld_amount, 42);
+        assert_eq!((view.current_fee.epoch, view.current_fee.transfer_fee_basis_points), (7, 100));
+        assert_eq!((view.next_fee.epoch, view.next_fee.transfer_fee_basis_points), (8, 250));
+        assert_eq!(view.next_fee.maximum_fee, 5_000);
+
+        let view = MintView::decode(&address, &data, 8).unwrap();
+        assert_eq!(view.current_fee.transfer_fee_basis_points, 250);
+
+        assert!(MintView::decode(&address, &[0; 82], 0).is_err());
+    }
+
+    #[test]
+    fn show_view_prints_and_serializes() {
+        use solanadeads_fee_router_client::default_sinks;
+        let mint = Pubkey::new_unique();
+        let sinks = default_sinks(Pubkey::new_unique(), Pubkey::new_unique(), &mint);
+        let router = Router::new(255, Pubkey::new_unique(), sinks, &mint, &spl_token_2022::ID);
+        let view = ShowView {
+            router: RouterView::new(&Pubkey::new_unique(), &router),
+            vault: VaultView {
+                address: Pubkey::new_unique().to_string(),
+                balance: 9,
+            },
+            mint: MintView::decode(&mint, &mint_data(Pubkey::new_unique()), 7).unwrap(),
+        };
+
+        let text = view.to_string();
+        assert!(text.contains(" 65.00%  "));
+        assert!(text.contains("100 bps, max 1000 (epoch 7)"));
+        let json: serde_json::Value = serde_json::to_value(&view).unwrap();
+        assert_eq!(json["router"]["splits"][0]["bps"], 6500);
+        assert_eq!(json["router"]["guardian"], serde_json::Value::Null);
+        assert_eq!(json["mint"]["next_fee"]["transfer_fee_basis_points"], 250);
+        assert_eq!(json["vault"]["balance"], 9);
+    }
+}
@@ -0,0 +1,419 @@
+// crates/solanadeads-fee-router-client/src/lib.rs
+
+//! ============================================================================
+//! Solana Deads — Fee Router client
+//! ----------------------------------------------------------------------------
+//! Address derivations and instruction builders for off-chain Rust callers,
+//! so services do not copy the seed constants out of the program:
+//!   • `router_pda`, `router_vault` and the sink token accounts
+//!   • rewards-program PDAs (`rewards-config`, `pool-registry-v2`,
+//!     `rewards-vault`) for the post-distribution sync
+//!   • `initialize_router`, `distribute_fees` and `harvest_and_distribute`
+//!   • packing harvest sources into packet-sized transactions and sizing
+//!     their compute-unit limits
+//!
+//! Everything is Token-2022: the router only runs on Token-2022 mints.
+//! ============================================================================
+
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anyhow::{bail, ensure, Context, Result};
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::config::RpcSimulateTransactionConfig;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::message::Message;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use solanadeads_fee_router::{
+    accounts, instruction, POOL_REGISTRY_SEED, REWARDS_CONFIG_SEED, REWARDS_PROGRAM_ID, REWARDS_VAULT_SEED,
+    SEED_NAMESPACE, SEED_ROUTER,
+};
+
+pub use solanadeads_fee_router::{
+    RewardsCpiConfig, Router, RouterStats, SinkConfig, SplitRecipient, ID as PROGRAM_ID,
+};
+
+// ------------------------------ Addresses ------------------------------------
+
+/// Router PDA for `mint`.
+pub fn router_pda(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[SEED_NAMESPACE, SEED_ROUTER, mint.as_ref()], &PROGRAM_ID).0
+}
+
+/// Router vault: the router PDA's Token-2022 ATA.
+pub fn router_vault(mint: &Pubkey) -> Pubkey {
+    token_account(&router_pda(mint), mint)
+}
+
+/// Token-2022 ATA of `owner` for `mint`.
+pub fn token_account(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, mint, &spl_token_2022::ID)
+}
+
+/// Treasury sink token account (ATA of `sinks.treasury_owner`).
+pub fn treasury_ata(sinks: &SinkConfig, mint: &Pubkey) -> Pubkey {
+    token_account(&sinks.treasury_owner, mint)
+}
+
+/// LP sink token account (ATA of `sinks.lp_owner`).
+pub fn lp_ata(sinks: &SinkConfig, mint: &Pubkey) -> Pubkey {
+    token_account(&sinks.lp_owner, mint)
+}
+
+/// Default split table recipients: [stakers, treasury ATA, LP ATA].
+pub fn sink_token_accounts(sinks: &SinkConfig, mint: &Pubkey) -> [Pubkey; 3] {
+    [sinks.stakers_wallet, treasury_ata(sinks, mint), lp_ata(sinks, mint)]
+}
+
+/// `rewards-config` PDA of `rewards_program` for `mint`.
+pub fn rewards_config(mint: &Pubkey, rewards_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[REWARDS_CONFIG_SEED, mint.as_ref()], rewards_program).0
+}
+
+/// `pool-registry-v2` PDA of `rewards_program` for `mint`.
+pub fn pool_registry(mint: &Pubkey, rewards_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[POOL_REGISTRY_SEED, mint.as_ref()], rewards_program).0
+}
+
+/// `rewards-vault` PDA of `rewards_program` for `mint`: the authority of
+/// the rewards vault token account.
+pub fn rewards_vault_authority(mint: &Pubkey, rewards_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[REWARDS_VAULT_SEED, mint.as_ref()], rewards_program).0
+}
+
+/// Rewards vault token account (ATA of the `rewards-vault` PDA), the usual
+/// `stakers_wallet`.
+pub fn rewards_vault(mint: &Pubkey, rewards_program: &Pubkey) -> Pubkey {
+    token_account(&rewards_vault_authority(mint, rewards_program), mint)
+}
+
+/// Sinks for a new router, paying stakers into the default rewards
+/// program's vault.
+pub fn default_sinks(treasury_owner: Pubkey, lp_owner: Pubkey, mint: &Pubkey) -> SinkConfig {
+    SinkConfig {
+        treasury_owner,
+        lp_owner,
+        stakers_wallet: rewards_vault(mint, &REWARDS_PROGRAM_ID),
+    }
+}
+
+/// Rewards-program accounts `harvest_and_distribute` passes to the sync CPI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RewardsAccounts {
+    pub program: Pubkey,
+    pub config: Pubkey,
+    pub pool_registry: Pubkey,
+    pub vault_authority: Pubkey,
+}
+
+impl RewardsAccounts {
+    pub fn new(mint: &Pubkey, rewards_program: &Pubkey) -> Self {
+        Self {
+            program: *rewards_program,
+            config: rewards_config(mint, rewards_program),
+            pool_registry: pool_registry(mint, rewards_program),
+            vault_authority: rewards_vault_authority(mint, rewards_program),
+        }
+    }
+
+    /// Accounts for a router's sync config; `None` while the sync is
+    /// disabled, in which case the router skips it.
+    pub fn for_router(mint: &Pubkey, rewards_cpi: &RewardsCpiConfig) -> Option<Self> {
+        rewards_cpi
+            .enabled
+            .then(|| Self::new(mint, &rewards_cpi.program_id))
+    }
+}
+
+// ------------------------------ Instructions ---------------------------------
+
+/// `initialize_router`, seeding the default split table for `sinks`.
+pub fn initialize_router(authority: &Pubkey, mint: &Pubkey, sinks: SinkConfig) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::InitializeRouter {
+            router: router_pda(mint),
+            authority: *authority,
+            system_program: system_program::ID,
+            mint: *mint,
+            token_program: spl_token_2022::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializeRouter { sinks }.data(),
+    }
+}
+
+/// Split table sink token accounts, writable and in table order: the
+/// leading `remaining_accounts` of every payout instruction.
+pub fn sink_metas(router: &Router) -> Vec<AccountMeta> {
+    router
+        .splits
+        .iter()
+        .map(|split| AccountMeta::new(split.recipient, false))
+        .collect()
+}
+
+/// `distribute_fees` of `amount` out of the vault, paying `router`'s split
+/// table.
+pub fn distribute_fees(caller: &Pubkey, mint: &Pubkey, router: &Router, amount: u64, decimals: u8) -> Instruction {
+    let mut metas = accounts::DistributeFees {
+        router: router_pda(mint),
+        caller: *caller,
+        mint: *mint,
+        token_program: spl_token_2022::ID,
+        associated_token_program: anchor_spl::associated_token::ID,
+        router_vault: router_vault(mint),
+    }
+    .to_account_metas(None);
+    metas.extend(sink_metas(router));
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: metas,
+        data: instruction::DistributeFees { amount, decimals }.data(),
+    }
+}
+
+/// Named accounts of `harvest_and_distribute` (also used by
+/// `withdraw_from_accounts_and_distribute` and `withdraw_and_distribute`),
+/// with the rewards accounts filled in from `router.rewards_cpi`.
+pub fn harvest_accounts(caller: &Pubkey, mint: &Pubkey, router: &Router) -> accounts::HarvestAndDistribute {
+    let rewards = RewardsAccounts::for_router(mint, &router.rewards_cpi);
+    accounts::HarvestAndDistribute {
+        router: router_pda(mint),
+        caller: *caller,
+        mint: *mint,
+        token_program: spl_token_2022::ID,
+        associated_token_program: anchor_spl::associated_token::ID,
+        router_vault: router_vault(mint),
+        stakers_wallet: router.stakers_wallet,
+        vault_authority_pda: rewards.map(|r| r.vault_authority),
+        rewards_program: rewards.map(|r| r.program),
+        rewards_config: rewards.map(|r| r.config),
+        pool_registry: rewards.map(|r| r.pool_registry),
+        system_program: system_program::ID,
+    }
+}
+
+/// `harvest_and_distribute` over `sources`, after `router`'s split table
+/// sinks. `pool_ids` is forwarded to the rewards sync (empty = all pools).
+pub fn harvest_and_distribute(
+    caller: &Pubkey,
+    mint: &Pubkey,
+    router: &Router,
+    sources: &[Pubkey],
+    pool_ids: Vec<u8>,
+) -> Instruction {
+    let mut metas = harvest_accounts(caller, mint, router).to_account_metas(None);
+    metas.extend(sink_metas(router));
+    metas.extend(sources.iter().map(|source| AccountMeta::new(*source, false)));
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: metas,
+        data: instruction::HarvestAndDistribute { pool_ids }.data(),
+    }
+}
+
+// ------------------------------ Transactions ---------------------------------
+
+/// Largest compute-unit limit a transaction may request.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Serialized size of a transaction carrying `ixs`, signatures included.
+pub fn tx_size(payer: &Pubkey, ixs: &[Instruction]) -> usize {
+    let tx = Transaction::new_unsigned(Message::new(ixs, Some(payer)));
+    bincode::serialized_size(&tx).map_or(usize::MAX, |size| size as usize)
+}
+
+/// Split `items` in order into runs of at most `max_len` that each satisfy
+/// `fits`, starting a new run whenever the next item would not fit. Fails if
+/// a single item does not fit on its own.
+pub fn chunk_by<T: Clone>(items: &[T], max_len: usize, mut fits: impl FnMut(&[T]) -> bool) -> Result<Vec<Vec<T>>> {
+    ensure!(max_len > 0, "max accounts per transaction must be at least 1");
+    let mut chunks = Vec::new();
+    let mut current: Vec<T> = Vec::new();
+    for item in items {
+        current.push(item.clone());
+        if current.len() <= max_len && fits(&current) {
+            continue;
+        }
+        current.pop();
+        ensure!(!current.is_empty(), "a single source does not fit in a transaction");
+        chunks.push(std::mem::take(&mut current));
+        current.push(item.clone());
+        ensure!(fits(&current), "a single source does not fit in a transaction");
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    Ok(chunks)
+}
+
+/// Compute-unit limit for a transaction that consumed `units` in
+/// simulation: 10% headroom, since the accounts it touches can change
+/// before it lands.
+pub fn compute_unit_limit(units: u64) -> u32 {
+    units.saturating_add(units / 10).min(u64::from(MAX_COMPUTE_UNIT_LIMIT)) as u32
+}
+
+/// Compute units `ixs` consume in simulation, paid by `payer` (no signature
+/// needed); `None` if they run out of the limit they set.
+pub fn simulate_units(rpc: &RpcClient, payer: &Pubkey, ixs: &[Instruction]) -> Result<Option<u64>> {
+    let tx = Transaction::new_unsigned(Message::new(ixs, Some(payer)));
+    let config = RpcSimulateTransactionConfig {
+        replace_recent_blockhash: true,
+        ..RpcSimulateTransactionConfig::default()
+    };
+    let sim = rpc.simulate_transaction_with_config(&tx, config)?.value;
+    match sim.err {
+        None => Ok(Some(sim.units_consumed.context("simulation reported no compute units")?)),
+        Some(TransactionError::InstructionError(_, InstructionError::ComputationalBudgetExceeded)) => Ok(None),
+        Some(err) => bail!("simulation failed: {err}\n{}", sim.logs.unwrap_or_default().join("\n")),
+    }
+}
+
+/// Pair each of `chunks` with its compute-unit limit, where `units` reports
+/// what a run consumes (`None` if it exceeded the maximum limit). A run
+/// whose limit would be over `max_units` is halved and both halves are
+/// measured again; order is kept. Fails if a single item does not fit.
+pub fn fit_compute<T>(
+    chunks: Vec<Vec<T>>,
+    max_units: u32,
+    mut units: impl FnMut(&[T]) -> Result<Option<u64>>,
+) -> Result<Vec<(Vec<T>, u32)>> {
+    let mut fitted = Vec::with_capacity(chunks.len());
+    let mut pending: Vec<Vec<T>> = chunks.into_iter().rev().collect();
+    while let Some(mut chunk) = pending.pop() {
+        match units(&chunk)?.map(compute_unit_limit) {
+            Some(limit) if limit <= max_units => fitted.push((chunk, limit)),
+            _ => {
+                ensure!(chunk.len() > 1, "a single source needs more than {max_units} compute units");
+                let tail = chunk.split_off(chunk.len() / 2);
+                pending.push(tail);
+                pending.push(chunk);
+            }
+        }
+    }
+    Ok(fitted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Discriminator;
+
+    fn router(mint: &Pubkey) -> Router {
+        let sinks = default_sinks(Pubkey::new_unique(), Pubkey::new_unique(), mint);
+        Router::new(255, Pubkey::new_unique(), sinks, mint, &spl_token_2022::ID)
+    }
+
+    #[test]
+    fn sinks_match_the_seeded_split_table() {
+        let mint = Pubkey::new_unique();
+        let sinks = default_sinks(Pubkey::new_unique(), Pubkey::new_unique(), &mint);
+        let router = Router::new(255, Pubkey::new_unique(), sinks, &mint, &spl_token_2022::ID);
+        let recipients: Vec<Pubkey> = router.splits.iter().map(|split| split.recipient).collect();
+        assert_eq!(recipients, sink_token_accounts(&sinks, &mint));
+        assert_eq!(
+            sinks.stakers_wallet,
+            token_account(&rewards_vault_authority(&mint, &REWARDS_PROGRAM_ID), &mint)
+        );
+    }
+
+    #[test]
+    fn harvest_fills_rewards_accounts() {
+        let mint = Pubkey::new_unique();
+        let mut router = router(&mint);
+        let caller = Pubkey::new_unique();
+        let sources = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let ix = harvest_and_distribute(&caller, &mint, &router, &sources, vec![3]);
+        let keys: Vec<Pubkey> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+
+        let rewards = RewardsAccounts::new(&mint, &REWARDS_PROGRAM_ID);
+        assert_eq!(
+            keys[..12],
+            [
+                router_pda(&mint),
+                caller,
+                mint,
+                spl_token_2022::ID,
+                anchor_spl::associated_token::ID,
+                router_vault(&mint),
+                router.stakers_wallet,
+                rewards.vault_authority,
+                REWARDS_PROGRAM_ID,
+                rewards.config,
+                rewards.pool_registry,
+                system_program::ID,
+            ]
+        );
+        assert_eq!(keys[12..15], sink_token_accounts(&router.sink_config(), &mint));
+        assert_eq!(keys[15..], sources);
+        assert!(ix.accounts[12..].iter().all(|meta| meta.is_writable));
+        assert_eq!(ix.data[..8], *instruction::HarvestAndDistribute::DISCRIMINATOR);
+        assert_eq!(ix.data[8..], [1, 0, 0, 0, 3]);
+
+        // A disabled sync leaves the optional slots empty (the program ID)
+        router.rewards_cpi.enabled = false;
+        let ix = harvest_and_distribute(&caller, &mint, &router, &sources, Vec::new());
+        assert!(ix.accounts[7..11].iter().all(|meta| meta.pubkey == PROGRAM_ID));
+    }
+
+    #[test]
+    fn distribute_and_initialize_target_the_router() {
+        let mint = Pubkey::new_unique();
+        let router = router(&mint);
+        let caller = Pubkey::new_unique();
+
+        let ix = distribute_fees(&caller, &mint, &router, 1_000, 6);
+        assert_eq!(ix.accounts[0].pubkey, router_pda(&mint));
+        assert_eq!(ix.accounts[5].pubkey, router_vault(&mint));
+        assert_eq!(ix.accounts.len(), 6 + router.splits.len());
+        assert_eq!(ix.data[..8], *instruction::DistributeFees::DISCRIMINATOR);
+
+        let sinks = router.sink_config();
+        let ix = initialize_router(&caller, &mint, sinks);
+        assert_eq!(ix.accounts[0].pubkey, router_pda(&mint));
+        assert!(ix.accounts[1].is_signer);
+        assert_eq!(ix.data[..8], *instruction::InitializeRouter::DISCRIMINATOR);
+    }
+
+    #[test]
+    fn chunk_by_respects_fit_and_cap() {
+        let items: Vec<u32> = (1..=10).collect();
+        let sum_under_12 = |chunk: &[u32]| chunk.iter().sum::<u32>() <= 12;
+        assert_eq!(
+            chunk_by(&items, 100, sum_under_12).unwrap(),
+            vec![vec![1, 2, 3, 4], vec![5, 6], vec![7], vec![8], vec![9], vec![10]]
+        );
+        assert_eq!(
+            chunk_by(&items[..5], 2, |_| true).unwrap(),
+            vec![vec![1, 2], vec![3, 4], vec![5]]
+        );
+        assert!(chunk_by(&[13u32], 2, sum_under_12).is_err());
+        assert!(chunk_by(&items, 0, |_| true).is_err());
+        assert!(chunk_by::<u32>(&[], 4, |_| true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn fit_compute_halves_chunks_over_the_cap() {
+        // 10_000 units per item, `None` past the 1.4M maximum
+        let units = |chunk: &[u32]| {
+            let used = 10_000 * chunk.len() as u64;
+            Ok((used <= u64::from(MAX_COMPUTE_UNIT_LIMIT)).then_some(used))
+        };
+        let chunks = vec![(1..=6).collect::<Vec<u32>>(), vec![7, 8], (9..=200).collect()];
+        let fitted = fit_compute(chunks, 50_000, units).unwrap();
+        let runs: Vec<Vec<u32>> = fitted.iter().map(|(run, _)| run.clone()).collect();
+        assert_eq!(runs.concat(), (1..=200).collect::<Vec<u32>>());
+        assert_eq!(runs[..3], [vec![1, 2, 3], vec![4, 5, 6], vec![7, 8]]);
+        for (run, limit) in &fitted {
+            assert_eq!(*limit, 11_000 * run.len() as u32);
+            assert!(*limit <= 50_000);
+        }
+
+        assert_eq!(compute_unit_limit(u64::MAX), MAX_COMPUTE_UNIT_LIMIT);
+        assert!(fit_compute(vec![vec![1u32]], 10_000, units).is_err());
+        assert!(fit_compute(vec![vec![1u32], vec![2]], 20_000, |_| Ok(None)).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+// crates/solanadeads-fee-router-client/tests/program.rs
+
+//! Instructions built by this crate are accepted by the program as-is:
+//! addresses, rewards PDAs and sink order all line up. Runs on the fixture
+//! in `solanadeads-fee-router-test-support`, which the program's own tests
+//! share, so the program does not need this crate as a dev-dependency.
+
+use solana_sdk::signature::{Keypair, Signer};
+use solanadeads_fee_router::RouterError;
+use solanadeads_fee_router_client as client;
+use solanadeads_fee_router_test_support::{assert_custom_error, Fixture, DECIMALS};
+
+#[tokio::test]
+async fn client_addresses_match_fixture() {
+    let fx = Fixture::new().await;
+    let mint = fx.mint;
+    assert_eq!(client::router_pda(&mint), fx.router);
+    assert_eq!(client::router_vault(&mint), fx.router_vault);
+    assert_eq!(
+        client::sink_token_accounts(&fx.sinks, &mint),
+        [fx.sinks.stakers_wallet, fx.treasury_wallet, fx.lp_wallet]
+    );
+    let rewards = client::RewardsAccounts::new(&mint, &solanadeads_fee_router::REWARDS_PROGRAM_ID);
+    assert_eq!(rewards.config, fx.rewards_config);
+    assert_eq!(rewards.pool_registry, fx.pool_registry);
+    assert_eq!(rewards.vault_authority, fx.vault_authority);
+    assert_eq!(client::default_sinks(fx.sinks.treasury_owner, fx.sinks.lp_owner, &mint), fx.sinks);
+}
+
+#[tokio::test]
+async fn client_instructions_run() {
+    let mut fx = Fixture::without_router().await;
+    let (mint, payer) = (fx.mint, fx.payer());
+    let ix = client::initialize_router(&payer, &mint, fx.sinks);
+    fx.process(&[ix], &[]).await.unwrap();
+    let router = fx.router_state().await;
+
+    let router_vault = fx.router_vault;
+    fx.mint_to(&router_vault, 10_000).await;
+    let ix = client::distribute_fees(&payer, &mint, &router, 10_000, DECIMALS);
+    fx.process(&[ix], &[]).await.unwrap();
+    assert_eq!(fx.balance(&router_vault).await, 0);
+
+    let sources = fx.fee_bearing_accounts(2, 1_000_000).await;
+    let ix = client::harvest_and_distribute(&payer, &mint, &router, &sources, vec![4]);
+    fx.process(&[ix], &[]).await.unwrap();
+    assert_eq!(fx.rewards_sync_pool_ids().await, vec![4]);
+    for source in &sources {
+        assert_eq!(fx.withheld(source).await, 0);
+    }
+
+    // The caller is whoever the builder is given; the router still checks it
+    let stranger = Keypair::new();
+    let ix = client::harvest_and_distribute(&stranger.pubkey(), &mint, &router, &[], Vec::new());
+    assert_custom_error(
+        fx.process(&[ix], &[&stranger]).await,
+        RouterError::UnauthorizedCaller.into(),
+    );
+}
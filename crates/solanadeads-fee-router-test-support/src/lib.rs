@@ -0,0 +1,635 @@
+// crates/solanadeads-fee-router-test-support/src/lib.rs
+
+//! Shared `solana-program-test` harness for the program's integration tests
+//! and the client crate's round-trip test.
+//!
+//! The router runs as a native builtin (no SBF toolchain needed); Token-2022
+//! and the ATA program are the BPF builds bundled with program-test. A small
+//! stand-in is registered at `REWARDS_PROGRAM_ID` that counts
+//! `sync_vault_and_distribute` calls and records the last `pool_ids` in
+//! `rewards_config`.
+
+use anchor_lang::solana_program::account_info::AccountInfo;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::{Instruction, InstructionError};
+use anchor_lang::solana_program::program_error::ProgramError;
+use anchor_lang::{AnchorDeserialize, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::associated_token::spl_associated_token_account::instruction::create_associated_token_account;
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+use solana_system_interface::instruction as system_instruction;
+use solanadeads_fee_router::rewards_cpi::SyncVaultAndDistributeArgs;
+use solanadeads_fee_router::{
+    SinkConfig, SplitRecipient, POOL_REGISTRY_SEED, REWARDS_CONFIG_SEED, REWARDS_PROGRAM_ID,
+    REWARDS_VAULT_SEED, SEED_NAMESPACE, SEED_ROUTER,
+};
+use spl_token_2022::extension::transfer_fee::instruction as fee_ix;
+use spl_token_2022::extension::transfer_fee::TransferFeeAmount;
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+use spl_token_2022::state::{Account as SplAccount, Mint as SplMint};
+
+pub const DECIMALS: u8 = 6;
+pub const FEE_BPS: u16 = 100; // 1.00%
+pub const MAX_FEE: u64 = 1_000_000_000;
+
+/// `sync_vault_and_distribute` discriminator expected by the stand-in.
+pub const SYNC_DISCRIMINATOR: [u8; 8] = [8, 138, 201, 54, 235, 135, 144, 204];
+
+fn router_entry(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    // Anchor's `entry` ties the slice and AccountInfo lifetimes together.
+    let accounts: &[AccountInfo] = unsafe { std::mem::transmute(accounts) };
+    solanadeads_fee_router::entry(program_id, accounts, data)
+}
+
+/// Bytes of `rewards_config` used by the stand-in: call counter, pool_ids
+/// length, then up to 55 pool ids.
+const REWARDS_CONFIG_LEN: usize = 64;
+
+/// Rewards program stand-in: checks the discriminator, bumps a call counter
+/// in `rewards_config` (account 0) and records the `pool_ids` it was sent.
+fn rewards_stub(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if data.len() < 8 || data[..8] != SYNC_DISCRIMINATOR {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let args = SyncVaultAndDistributeArgs::try_from_slice(&data[8..])
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    let mut config = accounts[0].try_borrow_mut_data()?;
+    let calls = u64::from_le_bytes(config[..8].try_into().unwrap()) + 1;
+    config[..8].copy_from_slice(&calls.to_le_bytes());
+    config[8] = args.pool_ids.len() as u8;
+    config[9..9 + args.pool_ids.len()].copy_from_slice(&args.pool_ids);
+    Ok(())
+}
+
+pub fn router_pda(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[SEED_NAMESPACE, SEED_ROUTER, mint.as_ref()],
+        &solanadeads_fee_router::ID,
+    )
+    .0
+}
+
+pub fn rewards_pda(seed: &[u8], mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[seed, mint.as_ref()], &REWARDS_PROGRAM_ID).0
+}
+
+pub fn ata(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, mint, &spl_token_2022::ID)
+}
+
+/// Mints `Fixture::create_mint_with` can make.
+#[derive(Clone, Copy)]
+pub enum TestMint {
+    /// Token-2022 with a TransferFeeConfig and this withdraw authority.
+    TransferFee(Option<Pubkey>),
+    /// Token-2022 without extensions.
+    Token2022,
+    /// Legacy SPL Token.
+    SplToken,
+}
+
+pub struct Fixture {
+    pub ctx: ProgramTestContext,
+    pub mint: Pubkey,
+    pub router: Pubkey,
+    pub router_vault: Pubkey,
+    pub sinks: SinkConfig,
+    pub treasury_wallet: Pubkey,
+    pub lp_wallet: Pubkey,
+    /// Token accounts of the router's split table, in table order.
+    pub split_sinks: Vec<Pubkey>,
+    pub rewards_config: Pubkey,
+    pub pool_registry: Pubkey,
+    pub vault_authority: Pubkey,
+}
+
+pub fn program_test(mint: &Pubkey) -> ProgramTest {
+    let mut pt = ProgramTest::new(
+        "solanadeads_fee_router",
+        solanadeads_fee_router::ID,
+        processor!(router_entry),
+    );
+    pt.prefer_bpf(false);
+    pt.add_program("rewards_stub", REWARDS_PROGRAM_ID, processor!(rewards_stub));
+
+    for seed in [REWARDS_CONFIG_SEED, POOL_REGISTRY_SEED] {
+        pt.add_account(
+            rewards_pda(seed, mint),
+            Account {
+                lamports: 1_000_000_000,
+                data: vec![0; REWARDS_CONFIG_LEN],
+                owner: REWARDS_PROGRAM_ID,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+    }
+    pt
+}
+
+impl Fixture {
+    /// Fee-bearing mint (withdraw authority = router PDA), sink ATAs and an
+    /// initialized router with the default split table.
+    pub async fn new() -> Self {
+        let mut fx = Self::without_router().await;
+        let ix = fx.initialize_ix(fx.mint, spl_token_2022::ID);
+        fx.process(&[ix], &[]).await.unwrap();
+        fx
+    }
+
+    /// `initialize_router` for `mint` at its router PDA, paid for and owned
+    /// by the payer.
+    pub fn initialize_ix(&self, mint: Pubkey, token_program: Pubkey) -> Instruction {
+        self.admin_ix(
+            solanadeads_fee_router::accounts::InitializeRouter {
+                router: router_pda(&mint),
+                authority: self.payer(),
+                system_program: solana_sdk::system_program::ID,
+                mint,
+                token_program,
+            },
+            solanadeads_fee_router::instruction::InitializeRouter { sinks: self.sinks },
+        )
+    }
+
+    /// Same mint and sink ATAs, but no router account yet.
+    pub async fn without_router() -> Self {
+        let mint_kp = Keypair::new();
+        let mint = mint_kp.pubkey();
+        let ctx = program_test(&mint).start_with_context().await;
+
+        let router = router_pda(&mint);
+        let vault_authority = rewards_pda(REWARDS_VAULT_SEED, &mint);
+        let sinks = SinkConfig {
+            treasury_owner: Pubkey::new_unique(),
+            lp_owner: Pubkey::new_unique(),
+            stakers_wallet: ata(&vault_authority, &mint),
+        };
+
+        let treasury_wallet = ata(&sinks.treasury_owner, &mint);
+        let lp_wallet = ata(&sinks.lp_owner, &mint);
+
+        let mut fx = Self {
+            ctx,
+            mint,
+            router,
+            router_vault: ata(&router, &mint),
+            sinks,
+            treasury_wallet,
+            lp_wallet,
+            split_sinks: vec![sinks.stakers_wallet, treasury_wallet, lp_wallet],
+            rewards_config: rewards_pda(REWARDS_CONFIG_SEED, &mint),
+            pool_registry: rewards_pda(POOL_REGISTRY_SEED, &mint),
+            vault_authority,
+        };
+
+        fx.create_mint(&mint_kp).await;
+        for owner in [router, sinks.treasury_owner, sinks.lp_owner, vault_authority] {
+            fx.create_ata(&owner).await;
+        }
+        fx
+    }
+
+    /// Plant a v1 router account (`bump` + `authority` only) at the PDA.
+    pub fn set_legacy_router(&mut self, authority: &Pubkey) {
+        use anchor_lang::Discriminator;
+        let (_, bump) = Pubkey::find_program_address(
+            &[SEED_NAMESPACE, SEED_ROUTER, self.mint.as_ref()],
+            &solanadeads_fee_router::ID,
+        );
+        let mut data = solanadeads_fee_router::Router::DISCRIMINATOR.to_vec();
+        data.push(bump);
+        data.extend_from_slice(authority.as_ref());
+        let lamports = solana_sdk::rent::Rent::default().minimum_balance(data.len());
+        let account = Account {
+            lamports,
+            data,
+            owner: solanadeads_fee_router::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.ctx.set_account(&self.router, &account.into());
+    }
+
+    pub fn migrate_ix(&self, authority: Pubkey) -> Instruction {
+        self.admin_ix(
+            solanadeads_fee_router::accounts::MigrateRouter {
+                router: self.router,
+                authority,
+                mint: self.mint,
+                token_program: spl_token_2022::ID,
+                system_program: solana_sdk::system_program::ID,
+            },
+            solanadeads_fee_router::instruction::MigrateRouter { sinks: self.sinks },
+        )
+    }
+
+    pub fn payer(&self) -> Pubkey {
+        self.ctx.payer.pubkey()
+    }
+
+    /// Send `ixs` signed by the payer plus `signers`, on a fresh blockhash.
+    pub async fn process(
+        &mut self,
+        ixs: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let blockhash = self.ctx.get_new_latest_blockhash().await.unwrap();
+        let mut all: Vec<&Keypair> = vec![&self.ctx.payer];
+        all.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(ixs, Some(&self.ctx.payer.pubkey()), &all, blockhash);
+        self.ctx.banks_client.process_transaction(tx).await
+    }
+
+    async fn create_mint(&mut self, mint_kp: &Keypair) {
+        let router = self.router;
+        self.create_mint_with(mint_kp, TestMint::TransferFee(Some(router))).await;
+    }
+
+    /// Create a mint of `kind` at `mint_kp`, with the payer as mint authority.
+    pub async fn create_mint_with(&mut self, mint_kp: &Keypair, kind: TestMint) {
+        let payer = self.payer();
+        let mint = mint_kp.pubkey();
+        let (token_program, extensions) = match kind {
+            TestMint::TransferFee(_) => (spl_token_2022::ID, vec![ExtensionType::TransferFeeConfig]),
+            TestMint::Token2022 => (spl_token_2022::ID, Vec::new()),
+            TestMint::SplToken => (anchor_spl::token::ID, Vec::new()),
+        };
+        let space = ExtensionType::try_calculate_account_len::<SplMint>(&extensions).unwrap();
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let mut ixs = vec![system_instruction::create_account(
+            &payer,
+            &mint,
+            rent.minimum_balance(space),
+            space as u64,
+            &token_program,
+        )];
+        if let TestMint::TransferFee(withdraw_authority) = kind {
+            ixs.push(
+                fee_ix::initialize_transfer_fee_config(
+                    &token_program,
+                    &mint,
+                    Some(&payer),
+                    withdraw_authority.as_ref(),
+                    FEE_BPS,
+                    MAX_FEE,
+                )
+                .unwrap(),
+            );
+        }
+        ixs.push(
+            spl_token_2022::instruction::initialize_mint2(&token_program, &mint, &payer, None, DECIMALS)
+                .unwrap(),
+        );
+        self.process(&ixs, &[mint_kp]).await.unwrap();
+    }
+
+    pub async fn create_ata(&mut self, owner: &Pubkey) -> Pubkey {
+        let payer = self.payer();
+        let ix = create_associated_token_account(&payer, owner, &self.mint, &spl_token_2022::ID);
+        self.process(&[ix], &[]).await.unwrap();
+        ata(owner, &self.mint)
+    }
+
+    /// A payer-owned token account for a second, unrelated mint.
+    pub async fn foreign_token_account(&mut self) -> Pubkey {
+        let other = Keypair::new();
+        self.create_mint(&other).await;
+        let payer = self.payer();
+        let ix = create_associated_token_account(&payer, &payer, &other.pubkey(), &spl_token_2022::ID);
+        self.process(&[ix], &[]).await.unwrap();
+        ata(&payer, &other.pubkey())
+    }
+
+    pub async fn mint_to(&mut self, account: &Pubkey, amount: u64) {
+        let payer = self.payer();
+        let ix = spl_token_2022::instruction::mint_to(
+            &spl_token_2022::ID,
+            &self.mint,
+            account,
+            &payer,
+            &[],
+            amount,
+        )
+        .unwrap();
+        self.process(&[ix], &[]).await.unwrap();
+    }
+
+    /// Fee-bearing transfer between two holders; the fee stays withheld on `to`.
+    pub async fn transfer_with_fee(&mut self, from_owner: &Keypair, to: &Pubkey, amount: u64) {
+        let from = ata(&from_owner.pubkey(), &self.mint);
+        let fee = expected_fee(amount);
+        let ix = fee_ix::transfer_checked_with_fee(
+            &spl_token_2022::ID,
+            &from,
+            &self.mint,
+            to,
+            &from_owner.pubkey(),
+            &[],
+            amount,
+            DECIMALS,
+            fee,
+        )
+        .unwrap();
+        self.process(&[ix], &[from_owner]).await.unwrap();
+    }
+
+    /// Holder ATAs with withheld fees: each holder sends `amount` to the next.
+    pub async fn fee_bearing_accounts(&mut self, count: usize, amount: u64) -> Vec<Pubkey> {
+        let sender = Keypair::new();
+        let sender_ata = self.create_ata(&sender.pubkey()).await;
+        self.mint_to(&sender_ata, amount * count as u64).await;
+
+        let mut out = Vec::with_capacity(count);
+        for _ in 0..count {
+            let holder = self.create_ata(&Pubkey::new_unique()).await;
+            self.transfer_with_fee(&sender, &holder, amount).await;
+            out.push(holder);
+        }
+        out
+    }
+
+    pub async fn account_data(&mut self, address: &Pubkey) -> Vec<u8> {
+        self.ctx
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .unwrap_or_else(|| panic!("missing account {address}"))
+            .data
+    }
+
+    pub async fn balance(&mut self, token_account: &Pubkey) -> u64 {
+        let data = self.account_data(token_account).await;
+        StateWithExtensions::<SplAccount>::unpack(&data).unwrap().base.amount
+    }
+
+    pub async fn withheld(&mut self, token_account: &Pubkey) -> u64 {
+        let data = self.account_data(token_account).await;
+        let state = StateWithExtensions::<SplAccount>::unpack(&data).unwrap();
+        u64::from(state.get_extension::<TransferFeeAmount>().unwrap().withheld_amount)
+    }
+
+    pub async fn rewards_sync_calls(&mut self) -> u64 {
+        let config = self.rewards_config;
+        let data = self.account_data(&config).await;
+        u64::from_le_bytes(data[..8].try_into().unwrap())
+    }
+
+    /// `pool_ids` received by the last rewards sync.
+    pub async fn rewards_sync_pool_ids(&mut self) -> Vec<u8> {
+        let config = self.rewards_config;
+        let data = self.account_data(&config).await;
+        data[9..9 + data[8] as usize].to_vec()
+    }
+
+    pub async fn router_state(&mut self) -> solanadeads_fee_router::Router {
+        use anchor_lang::AccountDeserialize;
+        let router = self.router;
+        let data = self.account_data(&router).await;
+        solanadeads_fee_router::Router::try_deserialize(&mut data.as_slice()).unwrap()
+    }
+
+    /// Simulate `get_stats` and decode its return data.
+    pub async fn simulate_stats(&mut self) -> solanadeads_fee_router::RouterStats {
+        use anchor_lang::AnchorDeserialize;
+        let ix = self.admin_ix(
+            solanadeads_fee_router::accounts::GetStats {
+                router: self.router,
+                mint: self.mint,
+            },
+            solanadeads_fee_router::instruction::GetStats {},
+        );
+        let blockhash = self.ctx.get_new_latest_blockhash().await.unwrap();
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&self.ctx.payer.pubkey()),
+            &[&self.ctx.payer],
+            blockhash,
+        );
+        let sim = self.ctx.banks_client.simulate_transaction(tx).await.unwrap();
+        let return_data = sim.simulation_details.unwrap().return_data.unwrap();
+        assert_eq!(return_data.program_id, solanadeads_fee_router::ID);
+        solanadeads_fee_router::RouterStats::try_from_slice(&return_data.data).unwrap()
+    }
+
+    /// Accounts for `harvest_and_distribute` with `caller` as the crank.
+    pub fn harvest_accounts(&self, caller: Pubkey) -> solanadeads_fee_router::accounts::HarvestAndDistribute {
+        solanadeads_fee_router::accounts::HarvestAndDistribute {
+            router: self.router,
+            caller,
+            mint: self.mint,
+            token_program: spl_token_2022::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            router_vault: self.router_vault,
+            stakers_wallet: self.sinks.stakers_wallet,
+            vault_authority_pda: Some(self.vault_authority),
+            rewards_program: Some(REWARDS_PROGRAM_ID),
+            rewards_config: Some(self.rewards_config),
+            pool_registry: Some(self.pool_registry),
+            system_program: solana_sdk::system_program::ID,
+        }
+    }
+
+    /// Authority-signed admin instruction (`accounts` built for this router).
+    pub fn admin_ix(&self, accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+        Instruction {
+            program_id: solanadeads_fee_router::ID,
+            accounts: accounts.to_account_metas(None),
+            data: data.data(),
+        }
+    }
+
+    /// `update_splits` signed by `authority`.
+    pub fn update_splits_ix(&self, authority: Pubkey, splits: Vec<SplitRecipient>) -> Instruction {
+        self.admin_ix(
+            solanadeads_fee_router::accounts::UpdateSplits {
+                router: self.router,
+                authority,
+                mint: self.mint,
+            },
+            solanadeads_fee_router::instruction::UpdateSplits { splits },
+        )
+    }
+
+    /// Replace the split table and pass its recipients as the sinks from now on.
+    pub async fn update_splits(&mut self, splits: Vec<SplitRecipient>) {
+        let ix = self.update_splits_ix(self.payer(), splits.clone());
+        self.process(&[ix], &[]).await.unwrap();
+        self.split_sinks = splits.iter().map(|split| split.recipient).collect();
+    }
+
+    pub async fn update_rewards_cpi(&mut self, rewards_cpi: solanadeads_fee_router::RewardsCpiConfig) {
+        let ix = self.admin_ix(
+            solanadeads_fee_router::accounts::UpdateRewardsCpi {
+                router: self.router,
+                authority: self.payer(),
+                mint: self.mint,
+            },
+            solanadeads_fee_router::instruction::UpdateRewardsCpi { rewards_cpi },
+        );
+        self.process(&[ix], &[]).await.unwrap();
+    }
+
+    pub async fn set_harvest_sinks(&mut self, harvest_sinks: bool) {
+        let ix = self.admin_ix(
+            solanadeads_fee_router::accounts::SetHarvestSinks {
+                router: self.router,
+                authority: self.payer(),
+                mint: self.mint,
+            },
+            solanadeads_fee_router::instruction::SetHarvestSinks { harvest_sinks },
+        );
+        self.process(&[ix], &[]).await.unwrap();
+    }
+
+    /// `distribute_fees` with `caller` as the crank and the split table sinks.
+    pub fn distribute_ix(&self, caller: Pubkey, amount: u64) -> Instruction {
+        let mut metas = solanadeads_fee_router::accounts::DistributeFees {
+            router: self.router,
+            caller,
+            mint: self.mint,
+            token_program: spl_token_2022::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            router_vault: self.router_vault,
+        }
+        .to_account_metas(None);
+        metas.extend(self.sink_metas());
+        Instruction {
+            program_id: solanadeads_fee_router::ID,
+            accounts: metas,
+            data: solanadeads_fee_router::instruction::DistributeFees {
+                amount,
+                decimals: DECIMALS,
+            }
+            .data(),
+        }
+    }
+
+    pub async fn pause(&mut self) {
+        let ix = self.admin_ix(
+            solanadeads_fee_router::accounts::SetPaused {
+                router: self.router,
+                signer: self.payer(),
+                mint: self.mint,
+            },
+            solanadeads_fee_router::instruction::Pause {},
+        );
+        self.process(&[ix], &[]).await.unwrap();
+    }
+
+    pub async fn mint_withheld(&mut self) -> u64 {
+        use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+        let mint = self.mint;
+        let data = self.account_data(&mint).await;
+        let state = StateWithExtensions::<SplMint>::unpack(&data).unwrap();
+        u64::from(state.get_extension::<TransferFeeConfig>().unwrap().withheld_amount)
+    }
+
+    /// `harvest_and_distribute` with the split table sinks followed by `sources`.
+    pub fn harvest_ix(
+        &self,
+        accounts: solanadeads_fee_router::accounts::HarvestAndDistribute,
+        sources: &[Pubkey],
+    ) -> Instruction {
+        self.harvest_ix_with_pool_ids(accounts, sources, Vec::new())
+    }
+
+    pub fn harvest_ix_with_pool_ids(
+        &self,
+        accounts: solanadeads_fee_router::accounts::HarvestAndDistribute,
+        sources: &[Pubkey],
+        pool_ids: Vec<u8>,
+    ) -> Instruction {
+        let mut metas = accounts.to_account_metas(None);
+        metas.extend(self.sink_metas());
+        metas.extend(
+            sources
+                .iter()
+                .map(|s| anchor_lang::solana_program::instruction::AccountMeta::new(*s, false)),
+        );
+        Instruction {
+            program_id: solanadeads_fee_router::ID,
+            accounts: metas,
+            data: solanadeads_fee_router::instruction::HarvestAndDistribute { pool_ids }.data(),
+        }
+    }
+
+    /// `withdraw_from_accounts_and_distribute` with the split table sinks followed by `sources`.
+    pub fn withdraw_from_accounts_ix(
+        &self,
+        accounts: solanadeads_fee_router::accounts::HarvestAndDistribute,
+        sources: &[Pubkey],
+    ) -> Instruction {
+        let mut ix = self.harvest_ix(accounts, sources);
+        ix.data = solanadeads_fee_router::instruction::WithdrawFromAccountsAndDistribute { pool_ids: Vec::new() }
+            .data();
+        ix
+    }
+
+    /// `harvest_only` over `sources` with `caller` as the crank.
+    pub fn harvest_only_ix(&self, caller: Pubkey, sources: &[Pubkey]) -> Instruction {
+        let mut metas = solanadeads_fee_router::accounts::HarvestOnly {
+            router: self.router,
+            caller,
+            mint: self.mint,
+            token_program: spl_token_2022::ID,
+        }
+        .to_account_metas(None);
+        metas.extend(
+            sources
+                .iter()
+                .map(|s| anchor_lang::solana_program::instruction::AccountMeta::new(*s, false)),
+        );
+        Instruction {
+            program_id: solanadeads_fee_router::ID,
+            accounts: metas,
+            data: solanadeads_fee_router::instruction::HarvestOnly {}.data(),
+        }
+    }
+
+    /// `withdraw_and_distribute` with the split table sinks.
+    pub fn withdraw_and_distribute_ix(
+        &self,
+        accounts: solanadeads_fee_router::accounts::HarvestAndDistribute,
+    ) -> Instruction {
+        let mut metas = accounts.to_account_metas(None);
+        metas.extend(self.sink_metas());
+        Instruction {
+            program_id: solanadeads_fee_router::ID,
+            accounts: metas,
+            data: solanadeads_fee_router::instruction::WithdrawAndDistribute { pool_ids: Vec::new() }
+                .data(),
+        }
+    }
+
+    /// Split table sinks, in table order.
+    pub fn sink_metas(&self) -> Vec<anchor_lang::solana_program::instruction::AccountMeta> {
+        self.split_sinks
+            .iter()
+            .map(|s| anchor_lang::solana_program::instruction::AccountMeta::new(*s, false))
+            .collect()
+    }
+}
+
+/// Transfer fee Token-2022 charges on `amount` with the fixture's config.
+pub fn expected_fee(amount: u64) -> u64 {
+    let fee = (amount as u128 * FEE_BPS as u128).div_ceil(10_000) as u64;
+    fee.min(MAX_FEE)
+}
+
+/// Assert the transaction failed with custom program error `code`.
+pub fn assert_custom_error(result: Result<(), BanksClientError>, code: u32) {
+    match result {
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::Custom(actual),
+        ))) => assert_eq!(actual, code, "unexpected custom error"),
+        other => panic!("expected custom error {code}, got {other:?}"),
+    }
+}
@@ -6,31 +6,44 @@
 //! Adds `harvest_and_distribute()` which:
 //!   1) Harvests withheld fees to the mint (from provided token accounts)
 //!   2) Withdraws withheld fees from mint → router_vault (PDA ATA)
-//!   3) Distributes router_vault per 65 / 17.5 / 17.5 (optionally grossed-up)
+//!   3) Distributes router_vault per the on-chain split table, grossed up so
+//!      each recipient nets its share after the transfer fee
 //!
-//! Mint requirements (Token-2022):
-//!   • TransferFeeConfig present on DEADS mint
-//!   • harvest_withheld_authority = router PDA
+//! The split table lives on the `Router` account and is seeded with the
+//! default 65 / 17.5 / 17.5 at init. `update_splits()` lets the authority
+//! change it without a redeploy.
+//!
+//! Mint requirements (Token-2022, checked by `initialize_router`):
+//!   • TransferFeeConfig present on the mint (DEADS or any sister token)
 //!   • withdraw_withheld_authority = router PDA
+//!   • harvesting to the mint is permissionless in Token-2022, so there is
+//!     no separate harvest authority to check
 //! ============================================================================
 
+// Build hygiene, not program logic: `#[program]` expands Anchor's IDL
+// instructions at the crate root, outside the module, and they still call
+// `AccountInfo::realloc`. An `allow` on the module does not reach them.
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::instruction::AccountMeta;
-use anchor_lang::solana_program::program::invoke_signed;
-use anchor_spl::associated_token::AssociatedToken;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::system_program;
+use anchor_spl::associated_token::{get_associated_token_address_with_program_id, AssociatedToken};
 use anchor_lang::prelude::InterfaceAccount;
 use anchor_spl::token_interface::{
     transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked,
 };
 use spl_token_2022::extension::transfer_fee::instruction as token2022_ix;
-use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use spl_token_2022::extension::transfer_fee::{TransferFeeAmount, TransferFeeConfig};
 use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
-use spl_token_2022::state::Mint as SplMint;
-use std::str::FromStr;
+use spl_token_2022::state::{Account as SplAccount, Mint as SplMint};
 
 #[cfg(not(feature = "no-entrypoint"))]
 use solana_security_txt::security_txt;
 
+pub mod rewards_cpi;
+
 declare_id!("DEADS3ucNHjN8iz3Cw65joYxgVdguNsjytHRqCs7QvzA");
 
 #[cfg(not(feature = "no-entrypoint"))]
@@ -49,46 +62,201 @@ security_txt! {
 pub const SEED_NAMESPACE: &[u8] = b"solanadeads";
 pub const SEED_ROUTER: &[u8] = b"fee-router-v1";  // Use v1 (already has withdraw authority)
 
-// Hard-set **owner wallets** (cluster-agnostic). Program derives ATAs at runtime.
-pub const TREASURY_OWNER: &str = "26xcb2Ygdj47BSsXTgQf4QHQw38jxMaKbENHyzwkaQA8";
-pub const LP_OWNER: &str = "4zrLoUzDrTSohZ4ay6uuQM5fAPbyPSMi31hTRCaaQx7y";
-
-// The Undertaker Rewards Vault - This is the rewards-vault ATA (Token Account)
-// Vault Authority PDA: 6vLUBJaJoN9YK4jKsTwhMReWyNm9DoWw9Eee1hRZWgDV
-// Vault ATA: 2SHAd8fzBFYnDvK8DBHYQkcjiVtxh2L7ondTQ1ECztFv
-// This vault receives 65% of all harvested fees, which then get distributed to active pools!
-// STAKERS_OWNER is the actual token account (ATA), not the authority
-pub const STAKERS_OWNER: &str = "2SHAd8fzBFYnDvK8DBHYQkcjiVtxh2L7ondTQ1ECztFv";
+// Router account layout. v1 held only `bump` + `authority`; later versions keep
+// that prefix, so `version` always sits right after it.
+pub const ROUTER_VERSION: u8 = 2;
+pub const LEGACY_ROUTER_V1_LEN: usize = 1 + 32;
+// Zeroed tail reserved for future fields. New fields go right before it and
+// shrink it by their size, so existing accounts read them as zero.
+pub const ROUTER_RESERVED: usize = 127;
 
-// Token-2022 DEADS mint (mainnet & devnet)
+// Token-2022 DEADS mint (mainnet & devnet). Routers are not limited to it:
+// any mint whose withdraw authority is its router PDA can be initialized.
 pub const DEADS_MINT: &str = "DEADsWJZaonaiZPFkrqEEBGf43mzA5uHeHpwgy9dW666";
 
-// Rewards Program ID (deployed on devnet/mainnet)
-pub const REWARDS_PROGRAM_ID: &str = "DEADZS7SrZMW5aGgXzgUis59iaQfjgdmnXMQuJJo7uAu";
+// Default rewards program (deployed on devnet/mainnet); configurable per router
+pub const REWARDS_PROGRAM_ID: Pubkey = pubkey!("DEADZS7SrZMW5aGgXzgUis59iaQfjgdmnXMQuJJo7uAu");
 
-// Splits (basis points)
+// Rewards program PDA seeds (each followed by the mint key)
+pub const REWARDS_CONFIG_SEED: &[u8] = b"rewards-config";
+pub const POOL_REGISTRY_SEED: &[u8] = b"pool-registry-v2";
+pub const REWARDS_VAULT_SEED: &[u8] = b"rewards-vault";
+
+// Default discriminator for the rewards program's `sync_vault_and_distribute`
+pub const SYNC_VAULT_AND_DISTRIBUTE_DISCRIMINATOR: [u8; 8] = [8, 138, 201, 54, 235, 135, 144, 204];
+
+// Default splits (basis points) seeded into `Router.splits` at init; LP takes the rest
 pub const STAKERS_BP: u16 = 6500;  // 65.00%
 pub const TREASURY_BP: u16 = 1750; // 17.50%
 
+// Split tables must sum to exactly this many basis points
+pub const BPS_DENOMINATOR: u16 = 10_000;
+
+// Upper bound on split table entries (sizes the Router account).
+// Every recipient costs one Token-2022 `transfer_checked` CPI. The value 8 was
+// not measured against compute-unit usage: the program-test harness runs the
+// router natively, where its own instructions are not metered. Callers size
+// the transaction's compute budget by simulating it.
+pub const MAX_SPLIT_RECIPIENTS: usize = 8;
+
+// Upper bound on crank operators stored on the Router
+pub const MAX_CRANK_OPERATORS: usize = 4;
+
 // Optional dust guard
 pub const MIN_DISTRIBUTE: u64 = 10;
 
+// Per-sink ledger slots; room for a full table plus the one it replaced
+pub const MAX_SINK_TOTALS: usize = 2 * MAX_SPLIT_RECIPIENTS;
+
 // ------------------------------ Events ---------------------------------------
 
 #[event]
 pub struct FeeDistribution {
-    pub stakers_amount: u64,
-    pub treasury_amount: u64,
-    pub lp_amount: u64,
+    pub mint: Pubkey,
+    pub caller: Pubkey,
+    /// Transfer fee the legs were grossed up for.
+    pub fee: AppliedFee,
+    /// One leg per split table entry, in table order.
+    pub legs: Vec<DistributionLeg>,
+    /// Vault amount offered to the split.
     pub total: u64,
+    /// Part of `total` left in the vault for the next distribution.
+    pub remainder: u64,
+}
+
+#[event]
+pub struct SplitsUpdated {
+    pub splits: Vec<SplitRecipient>,
+}
+
+#[event]
+pub struct SinksUpdated {
+    pub router: Pubkey,
+    pub previous: SinkConfig,
+    pub current: SinkConfig,
+}
+
+#[event]
+pub struct RewardsCpiUpdated {
+    pub router: Pubkey,
+    pub rewards_cpi: RewardsCpiConfig,
+}
+
+#[event]
+pub struct AuthorityProposed {
+    pub router: Pubkey,
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+}
+
+#[event]
+pub struct AuthorityAccepted {
+    pub router: Pubkey,
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
+
+#[event]
+pub struct AuthorityRenounced {
+    pub router: Pubkey,
+    pub previous_authority: Pubkey,
+}
+
+#[event]
+pub struct GuardianUpdated {
+    pub router: Pubkey,
+    pub guardian: Pubkey,
+}
+
+#[event]
+pub struct RouterPaused {
+    pub router: Pubkey,
+    pub by: Pubkey,
+}
+
+#[event]
+pub struct RouterUnpaused {
+    pub router: Pubkey,
+    pub by: Pubkey,
+}
+
+#[event]
+pub struct CrankOperatorsUpdated {
+    pub router: Pubkey,
+    pub crank_operators: Vec<Pubkey>,
+}
+
+#[event]
+pub struct PermissionlessModeSet {
+    pub router: Pubkey,
+    pub permissionless: bool,
+}
+
+#[event]
+pub struct RouterMigrated {
+    pub router: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+#[event]
+pub struct HarvestSinksSet {
+    pub router: Pubkey,
+    pub harvest_sinks: bool,
+}
+
+#[event]
+pub struct LenientSourcesSet {
+    pub router: Pubkey,
+    pub lenient_sources: bool,
+}
+
+#[event]
+pub struct HarvestChunk {
+    pub router: Pubkey,
+    pub sources: u32,
+    /// Increase in the mint's withheld amount.
+    pub harvested: u64,
 }
 
 #[event]
 pub struct HarvestRun {
+    pub mint: Pubkey,
+    pub caller: Pubkey,
+    /// Sources with withheld fees passed to the harvest (or withdraw) CPI.
+    /// Sinks harvested with `sinks_harvested` go to the same CPI but are not
+    /// counted here: their withheld fees are what earlier runs reported as
+    /// `recycled_fee` (see `recycled_fees_total`).
     pub sources: u32,
+    /// Valid sources left out because nothing was withheld on them.
+    pub empty_sources: u32,
+    /// Sum of `withheld_amount` over the harvested accounts, read beforehand
+    /// (harvested sinks included).
+    pub withheld_total: u64,
+    /// Increase in the mint's withheld amount from the harvest CPI
+    /// (0 when withdrawing from accounts).
+    pub harvested: u64,
     pub vault_before: u64,
+    /// What the withdraw CPI moved into the vault: from the mint, or from the
+    /// sources with `from_accounts`.
+    pub withdrawn: u64,
     pub distributed: u64,
     pub vault_after: u64,
+    /// Transfer fee the legs were grossed up for.
+    pub fee: AppliedFee,
+    /// The distribution's legs, as in its `FeeDistribution`.
+    pub legs: Vec<DistributionLeg>,
+    /// Whether the sink accounts were harvested along with the sources.
+    pub sinks_harvested: bool,
+    /// Whether fees were withdrawn straight from the sources
+    /// (`withdraw_from_accounts_and_distribute`) instead of via the mint.
+    pub from_accounts: bool,
+    /// Fees this run's distribution left withheld on the sinks.
+    pub recycled_fee: u64,
+    /// Router's lifetime `recycled_fees` after this run.
+    pub recycled_fees_total: u64,
+    /// Sources dropped in lenient mode (always empty otherwise).
+    pub skipped: Vec<SkippedSource>,
 }
 
 // ------------------------------ Errors ---------------------------------------
@@ -103,11 +271,53 @@ pub enum RouterError {
     InsufficientVaultBalance,
     #[msg("Provided decimals do not match the mint's decimals")]
     DecimalsMismatch,
+    #[msg("Split table must contain at least one recipient")]
+    EmptySplitTable,
+    #[msg("Split table has more recipients than the router can store")]
+    TooManyRecipients,
+    #[msg("Split table basis points must sum to 10,000")]
+    InvalidSplitTotal,
+    #[msg("Split table lists the same recipient more than once")]
+    DuplicateRecipient,
+    #[msg("Fewer sink accounts were provided than the split table requires")]
+    MissingSinkAccounts,
+    #[msg("Sink account does not match the split table entry at its position")]
+    SinkAccountMismatch,
+    #[msg("Proposed authority must not be the default pubkey")]
+    InvalidAuthority,
+    #[msg("No authority transfer is pending")]
+    NoPendingAuthority,
+    #[msg("Signer is not the pending authority")]
+    PendingAuthorityMismatch,
+    #[msg("Caller is not the admin or a crank operator, and permissionless mode is off")]
+    UnauthorizedCaller,
+    #[msg("Crank operator list has more entries than the router can store")]
+    TooManyCrankOperators,
+    #[msg("Router is paused")]
+    Paused,
+    #[msg("Signer is neither the router authority nor the guardian")]
+    NotAuthorityOrGuardian,
+    #[msg("Rewards program account is not the configured rewards CPI target")]
+    InvalidRewardsProgram,
+    #[msg("Rewards program account is not owned by the rewards program")]
+    InvalidRewardsAccountOwner,
+    #[msg("Mint has no Token-2022 TransferFeeConfig extension")]
+    MissingTransferFeeConfig,
+    #[msg("Mint withdraw_withheld_authority is not the router PDA")]
+    WithdrawAuthorityMismatch,
+    #[msg("Router is already at the current layout version")]
+    AlreadyMigrated,
+    #[msg("Crank operator list names the same key more than once")]
+    DuplicateCrankOperator,
+    #[msg("Crank operator must not be the default pubkey")]
+    InvalidCrankOperator,
+    #[msg("Rewards sync is enabled but its accounts were not provided")]
+    MissingRewardsAccounts,
 }
 
 #[error_code]
 pub enum ErrorCode {
-    #[msg("Sink account mint does not match the DEADS mint")]
+    #[msg("Sink account mint does not match the router mint")]
     InvalidMintForSink,
     #[msg("Sink account is for the wrong token program")]
     WrongTokenProgramForSink,
@@ -115,13 +325,268 @@ pub enum ErrorCode {
 
 // ------------------------------ State ----------------------------------------
 
+/// One entry of the split table: a destination token account and its weight.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SplitRecipient {
+    pub recipient: Pubkey,
+    pub bps: u16,
+}
+impl SplitRecipient {
+    pub const LEN: usize = 32 + 2;
+}
+
+/// Why lenient mode dropped a harvest source.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SkipReason {
+    /// No lamports or data left (closed account).
+    Closed,
+    /// Not owned by Token-2022.
+    NotToken2022,
+    /// Owned by Token-2022 but not a token account.
+    NotTokenAccount,
+    /// Token account for another mint.
+    WrongMint,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SkippedSource {
+    pub account: Pubkey,
+    pub reason: SkipReason,
+}
+
+/// What one split table entry actually received: `gross` left the vault,
+/// `fee` was withheld by Token-2022 and `net` landed in the recipient.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DistributionLeg {
+    pub recipient: Pubkey,
+    pub gross: u64,
+    pub fee: u64,
+    pub net: u64,
+}
+
+/// Transfer fee a distribution was solved with: the mint's fee for `epoch`,
+/// or no fee with `fallback` set when the mint has no TransferFeeConfig.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AppliedFee {
+    pub epoch: u64,
+    pub transfer_fee_basis_points: u16,
+    pub maximum_fee: u64,
+    pub fallback: bool,
+}
+
+impl AppliedFee {
+    /// Parameters for `solve_legs`; `None` solves without a fee.
+    fn params(&self) -> Option<(u16, u64)> {
+        (!self.fallback).then_some((self.transfer_fee_basis_points, self.maximum_fee))
+    }
+}
+
+/// Sink owners stored on the Router. Treasury and LP are **owner wallets**
+/// (the router derives their ATAs); `stakers_wallet` is the rewards vault
+/// token account itself, not its authority.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SinkConfig {
+    pub treasury_owner: Pubkey,
+    pub lp_owner: Pubkey,
+    pub stakers_wallet: Pubkey,
+}
+
+/// Post-distribution sync CPI into the rewards program.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RewardsCpiConfig {
+    pub enabled: bool,
+    pub program_id: Pubkey,
+    pub discriminator: [u8; 8],
+}
+impl RewardsCpiConfig {
+    pub const LEN: usize = 1 + 32 + 8;
+}
+impl Default for RewardsCpiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            program_id: REWARDS_PROGRAM_ID,
+            discriminator: SYNC_VAULT_AND_DISTRIBUTE_DISCRIMINATOR,
+        }
+    }
+}
+
+/// Lifetime payouts to one sink token account.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SinkTotal {
+    pub recipient: Pubkey,
+    pub gross: u64,
+    pub net: u64,
+}
+impl SinkTotal {
+    pub const LEN: usize = 32 + 8 + 8;
+}
+
+/// Running totals kept on the Router and returned by `get_stats`. Counters
+/// saturate rather than fail so the ledger can never block the crank.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RouterStats {
+    /// Withheld fees swept from token accounts into the mint by harvests.
+    pub total_harvested: u64,
+    /// Withheld fees withdrawn from the mint into the vault.
+    pub total_withdrawn: u64,
+    /// Gross paid out of the vault, all sinks.
+    pub total_distributed: u64,
+    pub harvest_runs: u64,
+    pub last_run_slot: u64,
+    pub last_run_timestamp: i64,
+    /// Per sink token account, in order of first payout.
+    pub sinks: Vec<SinkTotal>,
+    /// Folded totals of sinks evicted from `sinks` once they left the split
+    /// table and their slot was needed (`recipient` is unused).
+    pub retired: SinkTotal,
+}
+impl RouterStats {
+    pub const LEN: usize = 8 * 5 + 8 + (4 + MAX_SINK_TOTALS * SinkTotal::LEN) + SinkTotal::LEN;
+
+    /// Add one payout to the per-sink and overall totals.
+    fn record_payout(&mut self, legs: &[DistributionLeg], splits: &[SplitRecipient]) {
+        for leg in legs.iter().filter(|leg| leg.gross > 0) {
+            self.total_distributed = self.total_distributed.saturating_add(leg.gross);
+            let idx = match self.sinks.iter().position(|t| t.recipient == leg.recipient) {
+                Some(idx) => idx,
+                None if self.sinks.len() < MAX_SINK_TOTALS => {
+                    self.sinks.push(SinkTotal {
+                        recipient: leg.recipient,
+                        ..SinkTotal::default()
+                    });
+                    self.sinks.len() - 1
+                }
+                None => {
+                    // The table holds at most half the slots, so one is stale
+                    let idx = self
+                        .sinks
+                        .iter()
+                        .position(|t| splits.iter().all(|s| s.recipient != t.recipient))
+                        .unwrap_or(0);
+                    let evicted = self.sinks[idx];
+                    self.retired.gross = self.retired.gross.saturating_add(evicted.gross);
+                    self.retired.net = self.retired.net.saturating_add(evicted.net);
+                    self.sinks[idx] = SinkTotal {
+                        recipient: leg.recipient,
+                        ..SinkTotal::default()
+                    };
+                    idx
+                }
+            };
+            let total = &mut self.sinks[idx];
+            total.gross = total.gross.saturating_add(leg.gross);
+            total.net = total.net.saturating_add(leg.net);
+        }
+    }
+}
+
 #[account]
 pub struct Router {
     pub bump: u8,
+    /// `Pubkey::default()` once renounced.
     pub authority: Pubkey,
+    /// Layout version, `ROUTER_VERSION` once initialized or migrated.
+    pub version: u8,
+    /// Set by `propose_authority`, cleared by `accept_authority`.
+    pub pending_authority: Option<Pubkey>,
+    /// Keys allowed to run the crank besides the authority (admin).
+    pub crank_operators: Vec<Pubkey>,
+    /// When set, anyone may run the crank.
+    pub permissionless: bool,
+    /// Optional emergency key that may pause/unpause; `Pubkey::default()` if unset.
+    pub guardian: Pubkey,
+    /// While set, the crank instructions fail with `Paused`.
+    pub paused: bool,
+    pub treasury_owner: Pubkey,
+    pub lp_owner: Pubkey,
+    /// Rewards vault token account (target of the rewards CPI).
+    pub stakers_wallet: Pubkey,
+    pub rewards_cpi: RewardsCpiConfig,
+    /// When set, every harvest also sweeps the sink accounts.
+    pub harvest_sinks: bool,
+    /// Lifetime transfer fees withheld on the sinks by router payouts. These
+    /// flow back through the router when the sinks are harvested.
+    pub recycled_fees: u64,
+    pub stats: RouterStats,
+    pub splits: Vec<SplitRecipient>,
+    /// When set, invalid harvest sources are skipped instead of failing.
+    pub lenient_sources: bool,
+    pub reserved: [u8; ROUTER_RESERVED],
 }
 impl Router {
-    pub const LEN: usize = 1 + 32;
+    pub const LEN: usize = 1
+        + 32
+        + 1
+        + (1 + 32)
+        + (4 + MAX_CRANK_OPERATORS * 32)
+        + 1
+        + 32
+        + 1
+        + 32 * 3
+        + RewardsCpiConfig::LEN
+        + 1
+        + 8
+        + RouterStats::LEN
+        + (4 + MAX_SPLIT_RECIPIENTS * SplitRecipient::LEN)
+        + 1
+        + ROUTER_RESERVED;
+
+    /// Fresh current-version state with the default split table.
+    pub fn new(bump: u8, authority: Pubkey, sinks: SinkConfig, mint: &Pubkey, token_program: &Pubkey) -> Self {
+        Self {
+            bump,
+            authority,
+            version: ROUTER_VERSION,
+            pending_authority: None,
+            crank_operators: Vec::new(),
+            permissionless: false,
+            guardian: Pubkey::default(),
+            paused: false,
+            treasury_owner: sinks.treasury_owner,
+            lp_owner: sinks.lp_owner,
+            stakers_wallet: sinks.stakers_wallet,
+            rewards_cpi: RewardsCpiConfig::default(),
+            harvest_sinks: false,
+            recycled_fees: 0,
+            stats: RouterStats::default(),
+            splits: default_splits(&sinks, mint, token_program),
+            lenient_sources: false,
+            reserved: [0; ROUTER_RESERVED],
+        }
+    }
+
+    pub fn sink_config(&self) -> SinkConfig {
+        SinkConfig {
+            treasury_owner: self.treasury_owner,
+            lp_owner: self.lp_owner,
+            stakers_wallet: self.stakers_wallet,
+        }
+    }
+
+    pub fn is_authority_or_guardian(&self, key: &Pubkey) -> bool {
+        self.authority == *key || (self.guardian != Pubkey::default() && self.guardian == *key)
+    }
+
+    /// Admin, crank operator, or anyone while `permissionless` is set.
+    pub fn can_crank(&self, caller: &Pubkey) -> bool {
+        self.permissionless || self.authority == *caller || self.crank_operators.contains(caller)
+    }
+
+    /// Book a payout in the ledger and add the fees it left withheld on the
+    /// sinks to `recycled_fees`. Returns the fees of this payout.
+    pub fn record_payout(&mut self, legs: &[DistributionLeg]) -> Result<u64> {
+        self.stats.record_payout(legs, &self.splits);
+        let fees = legs
+            .iter()
+            .try_fold(0u64, |acc, leg| acc.checked_add(leg.fee))
+            .ok_or(RouterError::MathOverflow)?;
+        self.recycled_fees = self
+            .recycled_fees
+            .checked_add(fees)
+            .ok_or(RouterError::MathOverflow)?;
+        Ok(fees)
+    }
 }
 
 // ------------------------------ Accounts -------------------------------------
@@ -142,199 +607,670 @@ pub struct InitializeRouter<'info> {
 
     pub system_program: Program<'info, System>,
 
-    #[account(address = Pubkey::from_str(DEADS_MINT).unwrap())]
+    #[account(mint::token_program = token_program)]
     pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct DistributeFees<'info> {
+pub struct MigrateRouter<'info> {
+    /// CHECK: older layouts do not deserialize as `Router`; the handler checks
+    /// the discriminator, version and stored authority.
     #[account(
         mut,
         seeds = [SEED_NAMESPACE, SEED_ROUTER, mint.key().as_ref()],
-        bump
+        bump,
+        owner = crate::ID
     )]
-    pub router: Account<'info, Router>,
+    pub router: UncheckedAccount<'info>,
 
-    #[account(address = Pubkey::from_str(DEADS_MINT).unwrap())]
-    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
 
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
     pub token_program: Interface<'info, TokenInterface>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-
+    pub system_program: Program<'info, System>,
+}
 
+#[derive(Accounts)]
+pub struct UpdateSplits<'info> {
     #[account(
         mut,
-        associated_token::mint = mint,
-        associated_token::authority = router,
-        associated_token::token_program = token_program
+        seeds = [SEED_NAMESPACE, SEED_ROUTER, mint.key().as_ref()],
+        bump = router.bump,
+        has_one = authority
     )]
-    pub router_vault: InterfaceAccount<'info, TokenAccount>,
+    pub router: Account<'info, Router>,
+
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+}
 
-    /// CHECK: fixed owner; ATA is derived below
-    #[account(address = Pubkey::from_str(TREASURY_OWNER).unwrap())]
-    pub treasury_owner: UncheckedAccount<'info>,
+#[derive(Accounts)]
+pub struct UpdateSinks<'info> {
     #[account(
         mut,
-        associated_token::mint = mint,
-        associated_token::authority = treasury_owner,
-        associated_token::token_program = token_program
+        seeds = [SEED_NAMESPACE, SEED_ROUTER, mint.key().as_ref()],
+        bump = router.bump,
+        has_one = authority
     )]
-    pub treasury_wallet: InterfaceAccount<'info, TokenAccount>,
+    pub router: Account<'info, Router>,
+
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+}
 
-    /// CHECK: fixed owner; ATA is derived below
-    #[account(address = Pubkey::from_str(LP_OWNER).unwrap())]
-    pub lp_owner: UncheckedAccount<'info>,
+#[derive(Accounts)]
+pub struct UpdateRewardsCpi<'info> {
     #[account(
         mut,
-        associated_token::mint = mint,
-        associated_token::authority = lp_owner,
-        associated_token::token_program = token_program
+        seeds = [SEED_NAMESPACE, SEED_ROUTER, mint.key().as_ref()],
+        bump = router.bump,
+        has_one = authority
     )]
-    pub lp_pool_wallet: InterfaceAccount<'info, TokenAccount>,
+    pub router: Account<'info, Router>,
+
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+}
 
-    /// CHECK: fixed owner; ATA is derived below
-    #[account(address = Pubkey::from_str(STAKERS_OWNER).unwrap())]
-    pub stakers_owner: UncheckedAccount<'info>,
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
     #[account(
         mut,
-        associated_token::mint = mint,
-        associated_token::authority = stakers_owner,
-        associated_token::token_program = token_program
+        seeds = [SEED_NAMESPACE, SEED_ROUTER, mint.key().as_ref()],
+        bump = router.bump,
+        has_one = authority
     )]
-    pub stakers_wallet: InterfaceAccount<'info, TokenAccount>,
+    pub router: Account<'info, Router>,
+
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
 }
 
 #[derive(Accounts)]
-pub struct HarvestAndDistribute<'info> {
+pub struct AcceptAuthority<'info> {
     #[account(
         mut,
         seeds = [SEED_NAMESPACE, SEED_ROUTER, mint.key().as_ref()],
-        bump
+        bump = router.bump
     )]
     pub router: Account<'info, Router>,
 
+    pub new_authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+}
+
+#[derive(Accounts)]
+pub struct RenounceAuthority<'info> {
     #[account(
         mut,
-        address = Pubkey::from_str(DEADS_MINT).unwrap()
+        seeds = [SEED_NAMESPACE, SEED_ROUTER, mint.key().as_ref()],
+        bump = router.bump,
+        has_one = authority
     )]
-    pub mint: InterfaceAccount<'info, Mint>,
+    pub router: Account<'info, Router>,
 
-    pub token_program: Interface<'info, TokenInterface>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+}
 
+#[derive(Accounts)]
+pub struct SetCrankRoles<'info> {
     #[account(
         mut,
-        associated_token::mint = mint,
-        associated_token::authority = router,
-        associated_token::token_program = token_program
+        seeds = [SEED_NAMESPACE, SEED_ROUTER, mint.key().as_ref()],
+        bump = router.bump,
+        has_one = authority
     )]
-    pub router_vault: InterfaceAccount<'info, TokenAccount>,
+    pub router: Account<'info, Router>,
+
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+}
 
-    /// CHECK: fixed owner; ATA is derived below
-    #[account(address = Pubkey::from_str(TREASURY_OWNER).unwrap())]
-    pub treasury_owner: UncheckedAccount<'info>,
+#[derive(Accounts)]
+pub struct SetLenientSources<'info> {
     #[account(
         mut,
-        associated_token::mint = mint,
-        associated_token::authority = treasury_owner,
-        associated_token::token_program = token_program
+        seeds = [SEED_NAMESPACE, SEED_ROUTER, mint.key().as_ref()],
+        bump = router.bump,
+        has_one = authority
     )]
-    pub treasury_wallet: InterfaceAccount<'info, TokenAccount>,
+    pub router: Account<'info, Router>,
+
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+}
 
-    /// CHECK: fixed owner; ATA is derived below
-    #[account(address = Pubkey::from_str(LP_OWNER).unwrap())]
-    pub lp_owner: UncheckedAccount<'info>,
+#[derive(Accounts)]
+pub struct GetStats<'info> {
     #[account(
-        mut,
-        associated_token::mint = mint,
-        associated_token::authority = lp_owner,
-        associated_token::token_program = token_program
+        seeds = [SEED_NAMESPACE, SEED_ROUTER, mint.key().as_ref()],
+        bump = router.bump
     )]
-    pub lp_pool_wallet: InterfaceAccount<'info, TokenAccount>,
+    pub router: Account<'info, Router>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+}
 
-    /// CHECK: Rewards vault token account
+#[derive(Accounts)]
+pub struct SetHarvestSinks<'info> {
     #[account(
         mut,
-        address = Pubkey::from_str(STAKERS_OWNER).unwrap()
+        seeds = [SEED_NAMESPACE, SEED_ROUTER, mint.key().as_ref()],
+        bump = router.bump,
+        has_one = authority
     )]
-    pub stakers_wallet: InterfaceAccount<'info, TokenAccount>,
-    
-    // Rewards program accounts for CPI
-    /// CHECK: Vault authority PDA
-    pub vault_authority_pda: UncheckedAccount<'info>,
-    /// CHECK: Rewards program ID
-    pub rewards_program: UncheckedAccount<'info>,
-    /// CHECK: Rewards program config PDA
-    #[account(mut)]
-    pub rewards_config: UncheckedAccount<'info>,
-    /// CHECK: Rewards program pool registry PDA
-    pub pool_registry: UncheckedAccount<'info>,
-    pub system_program: Program<'info, System>,
+    pub router: Account<'info, Router>,
+
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
 }
 
-// ------------------------------ Program --------------------------------------
+#[derive(Accounts)]
+pub struct SetGuardian<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, SEED_ROUTER, mint.key().as_ref()],
+        bump = router.bump,
+        has_one = authority
+    )]
+    pub router: Account<'info, Router>,
 
-#[program]
-pub mod solanadeads_fee_router {
-    use super::*;
+    pub authority: Signer<'info>,
 
-    pub fn initialize_router(ctx: Context<InitializeRouter>) -> Result<()> {
-        let router = &mut ctx.accounts.router;
-        router.bump = ctx.bumps.router;
-        router.authority = ctx.accounts.authority.key();
-        Ok(())
-    }
+    pub mint: InterfaceAccount<'info, Mint>,
+}
 
-    /// Distribute a specific amount from the router vault.
-    pub fn distribute_fees(ctx: Context<DistributeFees>, amount: u64, decimals: u8) -> Result<()> {
-        // Token-2022 only
-        require_keys_eq!(
-            ctx.accounts.token_program.key(),
-            spl_token_2022::ID,
-            ErrorCode::WrongTokenProgramForSink
-        );
-        require!(amount >= MIN_DISTRIBUTE, RouterError::ZeroAmount);
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, SEED_ROUTER, mint.key().as_ref()],
+        bump = router.bump,
+        constraint = router.is_authority_or_guardian(&signer.key()) @ RouterError::NotAuthorityOrGuardian
+    )]
+    pub router: Account<'info, Router>,
 
-        // Ensure the vault can cover the requested amount
-        require!(
-            ctx.accounts.router_vault.amount >= amount,
-            RouterError::InsufficientVaultBalance
-        );
+    pub signer: Signer<'info>,
 
-        // Prefer the mint's decimals (ignore or assert the arg)
-        let decimals_from_mint = ctx.accounts.mint.decimals;
-        let _ = decimals; // or: require!(decimals == decimals_from_mint, RouterError::DecimalsMismatch);
+    pub mint: InterfaceAccount<'info, Mint>,
+}
 
-        let router = &ctx.accounts.router;
-        let mint_key = ctx.accounts.mint.key();
-        let seeds = [
-            SEED_NAMESPACE.as_ref(),
-            SEED_ROUTER.as_ref(),
-            mint_key.as_ref(),
+#[derive(Accounts)]
+pub struct DistributeFees<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, SEED_ROUTER, mint.key().as_ref()],
+        bump,
+        constraint = !router.paused @ RouterError::Paused,
+        constraint = router.can_crank(&caller.key()) @ RouterError::UnauthorizedCaller
+    )]
+    pub router: Account<'info, Router>,
+
+    pub caller: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = router,
+        associated_token::token_program = token_program
+    )]
+    pub router_vault: InterfaceAccount<'info, TokenAccount>,
+    // remaining_accounts: sink token accounts, in split table order
+}
+
+#[derive(Accounts)]
+pub struct HarvestAndDistribute<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, SEED_ROUTER, mint.key().as_ref()],
+        bump,
+        has_one = stakers_wallet @ RouterError::SinkAccountMismatch,
+        constraint = !router.paused @ RouterError::Paused,
+        constraint = router.can_crank(&caller.key()) @ RouterError::UnauthorizedCaller
+    )]
+    pub router: Account<'info, Router>,
+
+    pub caller: Signer<'info>,
+
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = router,
+        associated_token::token_program = token_program
+    )]
+    pub router_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Rewards vault token account (`router.stakers_wallet`)
+    #[account(mut)]
+    pub stakers_wallet: InterfaceAccount<'info, TokenAccount>,
+    
+    // Rewards program accounts for CPI (optional; the sync is skipped without them)
+    /// CHECK: Vault authority PDA; address checked against the rewards program derivation
+    #[account(
+        seeds = [REWARDS_VAULT_SEED, mint.key().as_ref()],
+        bump,
+        seeds::program = router.rewards_cpi.program_id
+    )]
+    pub vault_authority_pda: Option<UncheckedAccount<'info>>,
+    /// CHECK: Rewards program ID; address and executable checked
+    #[account(
+        address = router.rewards_cpi.program_id @ RouterError::InvalidRewardsProgram,
+        executable
+    )]
+    pub rewards_program: Option<UncheckedAccount<'info>>,
+    /// CHECK: Rewards program config PDA; address and owner checked
+    #[account(
+        mut,
+        seeds = [REWARDS_CONFIG_SEED, mint.key().as_ref()],
+        bump,
+        seeds::program = router.rewards_cpi.program_id,
+        owner = router.rewards_cpi.program_id @ RouterError::InvalidRewardsAccountOwner
+    )]
+    pub rewards_config: Option<UncheckedAccount<'info>>,
+    /// CHECK: Rewards program pool registry PDA; address and owner checked
+    #[account(
+        seeds = [POOL_REGISTRY_SEED, mint.key().as_ref()],
+        bump,
+        seeds::program = router.rewards_cpi.program_id,
+        owner = router.rewards_cpi.program_id @ RouterError::InvalidRewardsAccountOwner
+    )]
+    pub pool_registry: Option<UncheckedAccount<'info>>,
+    pub system_program: Program<'info, System>,
+    // remaining_accounts: sink token accounts (split table order), then fee-bearing sources
+}
+
+#[derive(Accounts)]
+pub struct HarvestOnly<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, SEED_ROUTER, mint.key().as_ref()],
+        bump = router.bump,
+        constraint = !router.paused @ RouterError::Paused,
+        constraint = router.can_crank(&caller.key()) @ RouterError::UnauthorizedCaller
+    )]
+    pub router: Account<'info, Router>,
+
+    pub caller: Signer<'info>,
+
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    // remaining_accounts: fee-bearing token accounts to harvest
+}
+
+// ------------------------------ Program --------------------------------------
+
+#[program]
+pub mod solanadeads_fee_router {
+    use super::*;
+
+    /// Create the router for `mint`. The mint must be Token-2022 with a
+    /// TransferFeeConfig whose withdraw authority is this router's PDA.
+    pub fn initialize_router(ctx: Context<InitializeRouter>, sinks: SinkConfig) -> Result<()> {
+        // Token-2022 only
+        require_keys_eq!(
+            ctx.accounts.token_program.key(),
+            spl_token_2022::ID,
+            ErrorCode::WrongTokenProgramForSink
+        );
+        let withdraw_authority = get_withdraw_withheld_authority(&ctx.accounts.mint.to_account_info())?
+            .ok_or(RouterError::WithdrawAuthorityMismatch)?;
+        require_keys_eq!(
+            withdraw_authority,
+            ctx.accounts.router.key(),
+            RouterError::WithdrawAuthorityMismatch
+        );
+
+        let router = Router::new(
+            ctx.bumps.router,
+            ctx.accounts.authority.key(),
+            sinks,
+            &ctx.accounts.mint.key(),
+            &ctx.accounts.token_program.key(),
+        );
+        // Sinks that share an owner (or a stakers wallet equal to a derived
+        // ATA) would seed a table with duplicate recipients
+        validate_splits(&router.splits)?;
+        ctx.accounts.router.set_inner(router);
+        Ok(())
+    }
+
+    /// Grow an older router account to the current layout in place, keeping
+    /// its PDA (and so the mint's withdraw authority). v1 stored no sinks, so
+    /// they are supplied here; everything else starts at the init defaults.
+    pub fn migrate_router(ctx: Context<MigrateRouter>, sinks: SinkConfig) -> Result<()> {
+        let router_ai = ctx.accounts.router.to_account_info();
+        let (bump, authority, from_version) = {
+            let data = router_ai.try_borrow_data()?;
+            require!(
+                data.len() >= 8 + LEGACY_ROUTER_V1_LEN && data[..8] == *Router::DISCRIMINATOR,
+                anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+            );
+            let version = if data.len() == 8 + LEGACY_ROUTER_V1_LEN {
+                1
+            } else {
+                data[8 + LEGACY_ROUTER_V1_LEN]
+            };
+            let authority = Pubkey::try_from(&data[9..8 + LEGACY_ROUTER_V1_LEN]).unwrap();
+            (data[8], authority, version)
+        };
+        require!(from_version < ROUTER_VERSION, RouterError::AlreadyMigrated);
+        // Same error as the `has_one = authority` check on current-layout routers
+        require_keys_eq!(
+            authority,
+            ctx.accounts.authority.key(),
+            anchor_lang::error::ErrorCode::ConstraintHasOne
+        );
+        require!(bump == ctx.bumps.router, anchor_lang::error::ErrorCode::ConstraintSeeds);
+
+        let router = Router::new(
+            bump,
+            authority,
+            sinks,
+            &ctx.accounts.mint.key(),
+            &ctx.accounts.token_program.key(),
+        );
+        validate_splits(&router.splits)?;
+
+        // Top up rent for the new size, then grow in place
+        let new_len = 8 + Router::LEN;
+        let rent = Rent::get()?.minimum_balance(new_len);
+        let shortfall = rent.saturating_sub(router_ai.lamports());
+        if shortfall > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.authority.to_account_info(),
+                        to: router_ai.clone(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+        router_ai.resize(new_len)?;
+        router.try_serialize(&mut &mut router_ai.try_borrow_mut_data()?[..])?;
+
+        emit!(RouterMigrated {
+            router: router_ai.key(),
+            from_version,
+            to_version: ROUTER_VERSION,
+        });
+        Ok(())
+    }
+
+    /// Replace the split table. Entries must sum to `BPS_DENOMINATOR`.
+    pub fn update_splits(ctx: Context<UpdateSplits>, splits: Vec<SplitRecipient>) -> Result<()> {
+        validate_splits(&splits)?;
+        ctx.accounts.router.splits = splits.clone();
+        emit!(SplitsUpdated { splits });
+        Ok(())
+    }
+
+    /// Replace the stored sink owners. Split table entries that pointed at an
+    /// old sink are re-pointed at its replacement; weights are unchanged.
+    pub fn update_sinks(ctx: Context<UpdateSinks>, sinks: SinkConfig) -> Result<()> {
+        let mint_key = ctx.accounts.mint.key();
+        let router = &mut ctx.accounts.router;
+        let previous = router.sink_config();
+
+        let old_dest = sink_destinations(&previous, &mint_key, &spl_token_2022::ID);
+        let new_dest = sink_destinations(&sinks, &mint_key, &spl_token_2022::ID);
+        for split in router.splits.iter_mut() {
+            if let Some(i) = old_dest.iter().position(|d| *d == split.recipient) {
+                split.recipient = new_dest[i];
+            }
+        }
+        validate_splits(&router.splits)?;
+
+        router.treasury_owner = sinks.treasury_owner;
+        router.lp_owner = sinks.lp_owner;
+        router.stakers_wallet = sinks.stakers_wallet;
+        emit!(SinksUpdated {
+            router: router.key(),
+            previous,
+            current: sinks,
+        });
+        Ok(())
+    }
+
+    /// Configure (or disable) the post-distribution rewards sync CPI.
+    pub fn update_rewards_cpi(ctx: Context<UpdateRewardsCpi>, rewards_cpi: RewardsCpiConfig) -> Result<()> {
+        let router = &mut ctx.accounts.router;
+        router.rewards_cpi = rewards_cpi;
+        emit!(RewardsCpiUpdated {
+            router: router.key(),
+            rewards_cpi,
+        });
+        Ok(())
+    }
+
+    /// Step 1 of an authority handover. The new key must sign `accept_authority`
+    /// before it takes effect; proposing again overwrites the pending key.
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+        require_keys_neq!(new_authority, Pubkey::default(), RouterError::InvalidAuthority);
+        let router = &mut ctx.accounts.router;
+        router.pending_authority = Some(new_authority);
+        emit!(AuthorityProposed {
+            router: router.key(),
+            authority: router.authority,
+            pending_authority: new_authority,
+        });
+        Ok(())
+    }
+
+    /// Step 2 of an authority handover, signed by the pending authority.
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let router = &mut ctx.accounts.router;
+        let pending = router.pending_authority.ok_or(RouterError::NoPendingAuthority)?;
+        require_keys_eq!(
+            pending,
+            ctx.accounts.new_authority.key(),
+            RouterError::PendingAuthorityMismatch
+        );
+
+        let previous_authority = router.authority;
+        router.authority = pending;
+        router.pending_authority = None;
+        emit!(AuthorityAccepted {
+            router: router.key(),
+            previous_authority,
+            new_authority: pending,
+        });
+        Ok(())
+    }
+
+    /// Permanently give up the authority. Authority-gated instructions fail afterwards.
+    pub fn renounce_authority(ctx: Context<RenounceAuthority>) -> Result<()> {
+        let router = &mut ctx.accounts.router;
+        let previous_authority = router.authority;
+        router.authority = Pubkey::default();
+        router.pending_authority = None;
+        emit!(AuthorityRenounced {
+            router: router.key(),
+            previous_authority,
+        });
+        Ok(())
+    }
+
+    /// Replace the crank operator list (admin only).
+    pub fn set_crank_operators(ctx: Context<SetCrankRoles>, crank_operators: Vec<Pubkey>) -> Result<()> {
+        validate_crank_operators(&crank_operators)?;
+        let router = &mut ctx.accounts.router;
+        router.crank_operators = crank_operators.clone();
+        emit!(CrankOperatorsUpdated {
+            router: router.key(),
+            crank_operators,
+        });
+        Ok(())
+    }
+
+    /// Open (or close) the crank to every signer (admin only).
+    pub fn set_permissionless(ctx: Context<SetCrankRoles>, permissionless: bool) -> Result<()> {
+        let router = &mut ctx.accounts.router;
+        router.permissionless = permissionless;
+        emit!(PermissionlessModeSet {
+            router: router.key(),
+            permissionless,
+        });
+        Ok(())
+    }
+
+    /// Also sweep the sink accounts on every harvest, so fees withheld by
+    /// router payouts come straight back (authority only).
+    pub fn set_harvest_sinks(ctx: Context<SetHarvestSinks>, harvest_sinks: bool) -> Result<()> {
+        let router = &mut ctx.accounts.router;
+        router.harvest_sinks = harvest_sinks;
+        emit!(HarvestSinksSet {
+            router: router.key(),
+            harvest_sinks,
+        });
+        Ok(())
+    }
+
+    /// Skip invalid harvest sources (closed, wrong mint, not Token-2022)
+    /// instead of failing the whole harvest (authority only).
+    pub fn set_lenient_sources(ctx: Context<SetLenientSources>, lenient_sources: bool) -> Result<()> {
+        let router = &mut ctx.accounts.router;
+        router.lenient_sources = lenient_sources;
+        emit!(LenientSourcesSet {
+            router: router.key(),
+            lenient_sources,
+        });
+        Ok(())
+    }
+
+    /// Read-only: return the ledger (simulate the transaction and decode the
+    /// return data as `RouterStats`).
+    pub fn get_stats(ctx: Context<GetStats>) -> Result<RouterStats> {
+        Ok(ctx.accounts.router.stats.clone())
+    }
+
+    /// Set (or clear with `Pubkey::default()`) the guardian key (authority only).
+    pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+        let router = &mut ctx.accounts.router;
+        router.guardian = guardian;
+        emit!(GuardianUpdated {
+            router: router.key(),
+            guardian,
+        });
+        Ok(())
+    }
+
+    /// Emergency stop for the crank (authority or guardian).
+    pub fn pause(ctx: Context<SetPaused>) -> Result<()> {
+        let router = &mut ctx.accounts.router;
+        router.paused = true;
+        emit!(RouterPaused {
+            router: router.key(),
+            by: ctx.accounts.signer.key(),
+        });
+        Ok(())
+    }
+
+    /// Resume the crank (authority or guardian).
+    pub fn unpause(ctx: Context<SetPaused>) -> Result<()> {
+        let router = &mut ctx.accounts.router;
+        router.paused = false;
+        emit!(RouterUnpaused {
+            router: router.key(),
+            by: ctx.accounts.signer.key(),
+        });
+        Ok(())
+    }
+
+    /// Distribute a specific amount from the router vault.
+    /// `remaining_accounts` must be the sink token accounts in split table order.
+    pub fn distribute_fees<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, DistributeFees<'info>>,
+        amount: u64,
+        decimals: u8,
+    ) -> Result<()> {
+        // Token-2022 only
+        require_keys_eq!(
+            ctx.accounts.token_program.key(),
+            spl_token_2022::ID,
+            ErrorCode::WrongTokenProgramForSink
+        );
+        require!(amount >= MIN_DISTRIBUTE, RouterError::ZeroAmount);
+
+        // Ensure the vault can cover the requested amount
+        require!(
+            ctx.accounts.router_vault.amount >= amount,
+            RouterError::InsufficientVaultBalance
+        );
+
+        // Prefer the mint's decimals (ignore or assert the arg)
+        let decimals_from_mint = ctx.accounts.mint.decimals;
+        let _ = decimals; // or: require!(decimals == decimals_from_mint, RouterError::DecimalsMismatch);
+
+        let router = &ctx.accounts.router;
+        let mint_key = ctx.accounts.mint.key();
+        let seeds = [
+            SEED_NAMESPACE,
+            SEED_ROUTER,
+            mint_key.as_ref(),
             &[router.bump],
         ];
         let signer = &[&seeds[..]];
 
-        let fee_params = get_fee_params(&ctx.accounts.mint.to_account_info())?;
+        let fee = applied_fee(&ctx.accounts.mint.to_account_info())?;
+        let sinks = split_sink_accounts(
+            ctx.remaining_accounts,
+            &ctx.accounts.router,
+            &ctx.accounts.mint.key(),
+            &ctx.accounts.token_program.key(),
+        )?;
 
-        distribute_now(
+        let legs = distribute_now(
             &ctx.accounts.token_program,
             &ctx.accounts.router,
             &ctx.accounts.mint,
             &ctx.accounts.router_vault,
-            &ctx.accounts.stakers_wallet,
-            &ctx.accounts.treasury_wallet,
-            &ctx.accounts.lp_pool_wallet,
+            sinks,
             signer,
             amount,
             decimals_from_mint,
-            fee_params,
-        )
+            ctx.accounts.caller.key(),
+            fee,
+        )?;
+        ctx.accounts.router.record_payout(&legs)?;
+        Ok(())
     }
 
     /// Harvest withheld fees, withdraw to vault, then distribute.
-    /// `remaining_accounts` should be the list of **fee-bearing token accounts** to harvest from.
-    pub fn harvest_and_distribute<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, HarvestAndDistribute<'info>>) -> Result<()> {
+    /// `remaining_accounts` should be the sink token accounts in split table order,
+    /// followed by the list of **fee-bearing token accounts** to harvest from.
+    /// `pool_ids` is forwarded to the rewards sync; empty means all active pools.
+    pub fn harvest_and_distribute<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, HarvestAndDistribute<'info>>,
+        pool_ids: Vec<u8>,
+    ) -> Result<()> {
         // Token-2022 only
         require_keys_eq!(
             ctx.accounts.token_program.key(),
@@ -342,354 +1278,748 @@ pub mod solanadeads_fee_router {
             ErrorCode::WrongTokenProgramForSink
         );
 
-        let router = &ctx.accounts.router;
-        let mint_key = ctx.accounts.mint.key();
-        let seeds = [
-            SEED_NAMESPACE.as_ref(),
-            SEED_ROUTER.as_ref(),
-            mint_key.as_ref(),
-            &[router.bump],
-        ];
-        let signer = &[&seeds[..]];
-
         if let Some((bps, max_fee)) = get_fee_params(&ctx.accounts.mint.to_account_info())? {
             msg!("Transfer-Fee (epoch): {} bps, max {}", bps, max_fee);
         } else {
             msg!("Transfer-Fee config not found or no epoch fee set on mint");
         }
 
-        // Sanity-check remaining fee-bearing accounts: correct owner & mint
-        // remaining_accounts are just the fee-bearing token accounts (no mint)
+        // Leading remaining_accounts are the split table sinks
+        let sinks = split_sink_accounts(
+            ctx.remaining_accounts,
+            &ctx.accounts.router,
+            &ctx.accounts.mint.key(),
+            &ctx.accounts.token_program.key(),
+        )?;
+        let sources = &ctx.remaining_accounts[sinks.len()..];
+        let harvest_sinks = ctx.accounts.router.harvest_sinks;
+
         // Allow empty harvests (no-op if no fees to collect)
-        if ctx.remaining_accounts.is_empty() {
+        if sources.is_empty() && !harvest_sinks {
             msg!("No fee-bearing accounts provided - skipping harvest");
             return Ok(());
         }
-        
-        for acc in ctx.remaining_accounts.iter() {
-            require_keys_eq!(
-                *acc.owner,
-                ctx.accounts.token_program.key(),
-                ErrorCode::WrongTokenProgramForSink
-            );
-            let mut data: &[u8] = &acc.try_borrow_data()?;
-            let ta = anchor_spl::token_interface::TokenAccount::try_deserialize(&mut data)?;
-            require_keys_eq!(ta.mint, ctx.accounts.mint.key(), ErrorCode::InvalidMintForSink);
+
+        let (harvest_list, mut summary) = collect_sources(
+            &ctx.accounts.router,
+            sinks,
+            sources,
+            &ctx.accounts.mint.key(),
+            &ctx.accounts.token_program.key(),
+        )?;
+
+        // 1) Harvest → mint
+        if !harvest_list.is_empty() {
+            summary.harvested = harvest_to_mint(
+                &ctx.accounts.token_program,
+                &ctx.accounts.mint,
+                harvest_list.iter(),
+            )?;
         }
+        let stats = &mut ctx.accounts.router.stats;
+        stats.total_harvested = stats.total_harvested.saturating_add(summary.harvested);
 
-        // Snapshot vault before
-        let vault_before = ctx.accounts.router_vault.amount;
+        withdraw_and_settle(ctx.accounts, sinks, pool_ids, summary, WithdrawFrom::Mint)
+    }
 
-        // 1) Harvest → mint from provided fee-bearing token accounts
-        let mut ix_harvest = token2022_ix::harvest_withheld_tokens_to_mint(
-            &ctx.accounts.token_program.key(),
+    /// Alternative to `harvest_and_distribute`: withdraw withheld fees
+    /// straight from the fee-bearing accounts to the vault
+    /// (`withdraw_withheld_tokens_from_accounts`, signed by the router PDA as
+    /// withdraw authority), then distribute. One CPI fewer, and the mint's
+    /// withheld balance is left untouched. Same accounts and
+    /// `remaining_accounts` layout as `harvest_and_distribute`.
+    pub fn withdraw_from_accounts_and_distribute<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, HarvestAndDistribute<'info>>,
+        pool_ids: Vec<u8>,
+    ) -> Result<()> {
+        // Token-2022 only
+        require_keys_eq!(
+            ctx.accounts.token_program.key(),
+            spl_token_2022::ID,
+            ErrorCode::WrongTokenProgramForSink
+        );
+
+        let sinks = split_sink_accounts(
+            ctx.remaining_accounts,
+            &ctx.accounts.router,
             &ctx.accounts.mint.key(),
-            &[],
-        )
-        .unwrap();
-        // Add fee accounts to the harvest instruction
-        for acc in ctx.remaining_accounts.iter() {
-            ix_harvest.accounts.push(AccountMeta::new(acc.key(), false));
+            &ctx.accounts.token_program.key(),
+        )?;
+        let sources = &ctx.remaining_accounts[sinks.len()..];
+
+        if sources.is_empty() && !ctx.accounts.router.harvest_sinks {
+            msg!("No fee-bearing accounts provided - skipping withdraw");
+            return Ok(());
         }
-        // Create account infos: [mint, fee_accounts...]
-        let mut harvest_account_infos = vec![ctx.accounts.mint.to_account_info()];
-        harvest_account_infos.extend(ctx.remaining_accounts.iter().map(|acc| acc.clone()));
-        invoke_signed(&ix_harvest, &harvest_account_infos, signer)?;
 
-        // 2) Withdraw withheld → router_vault (authority = router PDA)
-        let ix_withdraw = token2022_ix::withdraw_withheld_tokens_from_mint(
-            &ctx.accounts.token_program.key(),
+        let (withdraw_list, mut summary) = collect_sources(
+            &ctx.accounts.router,
+            sinks,
+            sources,
             &ctx.accounts.mint.key(),
-            &ctx.accounts.router_vault.key(),
-            &ctx.accounts.router.key(),
-            &[],
+            &ctx.accounts.token_program.key(),
+        )?;
+        summary.from_accounts = true;
+
+        withdraw_and_settle(
+            ctx.accounts,
+            sinks,
+            pool_ids,
+            summary,
+            WithdrawFrom::Accounts(&withdraw_list),
         )
-        .unwrap();
-        // AccountInfos must match ix metas: [mint, destination (vault), authority (router)]
-        let infos_withdraw = vec![
-            ctx.accounts.mint.to_account_info(),
-            ctx.accounts.router_vault.to_account_info(),
-            ctx.accounts.router.to_account_info(),
-        ];
-        invoke_signed(&ix_withdraw, &infos_withdraw[..], signer)?;
+    }
 
-        // 3) Re-read vault AFTER withdraw, then distribute that fresh balance
-        ctx.accounts.router_vault.reload()?;
-        let amount = ctx.accounts.router_vault.amount;
-        msg!("Vault balance after withdraw: {} lamports", amount);
-        
-        if amount < MIN_DISTRIBUTE {
-            msg!("Vault balance {} < MIN_DISTRIBUTE {}, skipping distribution", amount, MIN_DISTRIBUTE);
+    /// Harvest one chunk of fee-bearing accounts to the mint, without
+    /// withdrawing or distributing. `remaining_accounts` are the accounts to
+    /// sweep; Token-2022 skips (and logs) any it cannot harvest from, so they
+    /// are not deserialized here. Settle with `withdraw_and_distribute`.
+    pub fn harvest_only<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, HarvestOnly<'info>>,
+    ) -> Result<()> {
+        // Token-2022 only
+        require_keys_eq!(
+            ctx.accounts.token_program.key(),
+            spl_token_2022::ID,
+            ErrorCode::WrongTokenProgramForSink
+        );
+        if ctx.remaining_accounts.is_empty() {
+            msg!("No fee-bearing accounts provided - skipping harvest");
             return Ok(());
         }
 
-        let decimals_from_mint = ctx.accounts.mint.decimals;
-        let fee_params = get_fee_params(&ctx.accounts.mint.to_account_info())?;
-
-        distribute_now(
+        let harvested = harvest_to_mint(
             &ctx.accounts.token_program,
-            &ctx.accounts.router,
             &ctx.accounts.mint,
-            &ctx.accounts.router_vault,
-            &ctx.accounts.stakers_wallet,
-            &ctx.accounts.treasury_wallet,
-            &ctx.accounts.lp_pool_wallet,
-            signer,
-            amount,
-            decimals_from_mint,
-            fee_params,
+            ctx.remaining_accounts.iter(),
         )?;
+        let stats = &mut ctx.accounts.router.stats;
+        stats.total_harvested = stats.total_harvested.saturating_add(harvested);
 
-        // Re-read for `vault_after`
-        ctx.accounts.router_vault.reload()?;
-        let vault_after = ctx.accounts.router_vault.amount;
-
-        // CPI: Call rewards program's sync_vault_and_distribute
-        // This triggers automatic distribution of deposited rewards to active pools
-        let (stakers_amount, _, _) = compute_splits(amount)?;
-        if stakers_amount > 0 {
-            msg!("Calling rewards program sync_vault_and_distribute via CPI");
-            
-            // Discriminator for sync_vault_and_distribute
-            let discriminator: [u8; 8] = [8, 138, 201, 54, 235, 135, 144, 204];
-            
-            // Empty pool_ids - rewards program will use its own active_pool_ids from config
-            let pool_ids: Vec<u8> = vec![];
-            
-            // Encode instruction data: discriminator + Vec<u8> (length + elements)
-            let mut data = discriminator.to_vec();
-            data.extend_from_slice(&(pool_ids.len() as u32).to_le_bytes());
-            data.extend_from_slice(&pool_ids);
-            
-            // Derive rewards program PDAs
-            let (rewards_config, _) = Pubkey::find_program_address(
-                &[b"rewards-config", ctx.accounts.mint.key().as_ref()],
-                &ctx.accounts.rewards_program.key()
-            );
-            
-            let (pool_registry, _) = Pubkey::find_program_address(
-                &[b"pool-registry-v2", ctx.accounts.mint.key().as_ref()],
-                &ctx.accounts.rewards_program.key()
-            );
-            
-            let (vault_authority_pda, _) = Pubkey::find_program_address(
-                &[b"rewards-vault", ctx.accounts.mint.key().as_ref()],
-                &ctx.accounts.rewards_program.key()
-            );
-            
-            // Accounts: config, pool_registry, mint, vault_authority, vault, token_program, system_program
-            let cpi_accounts = vec![
-                AccountMeta::new(rewards_config, false),
-                AccountMeta::new_readonly(pool_registry, false),
-                AccountMeta::new_readonly(ctx.accounts.mint.key(), false),
-                AccountMeta::new_readonly(vault_authority_pda, false),
-                AccountMeta::new(ctx.accounts.stakers_wallet.key(), false),
-                AccountMeta::new_readonly(ctx.accounts.token_program.key(), false),
-                AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),
-            ];
-            
-            let cpi_instruction = anchor_lang::solana_program::instruction::Instruction {
-                program_id: ctx.accounts.rewards_program.key(),
-                accounts: cpi_accounts,
-                data,
-            };
-            
-            let cpi_account_infos = vec![
-                ctx.accounts.rewards_config.to_account_info(),
-                ctx.accounts.pool_registry.to_account_info(),
-                ctx.accounts.mint.to_account_info(),
-                ctx.accounts.vault_authority_pda.to_account_info(),
-                ctx.accounts.stakers_wallet.to_account_info(),
-                ctx.accounts.token_program.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ];
-            
-            anchor_lang::solana_program::program::invoke(
-                &cpi_instruction,
-                &cpi_account_infos,
-            )?;
-            
-            msg!("✅ Rewards vault synced and distributed via CPI");
-        }
-
-        emit!(HarvestRun {
+        emit!(HarvestChunk {
+            router: ctx.accounts.router.key(),
             sources: ctx.remaining_accounts.len() as u32,
-            vault_before,
-            distributed: amount,
-            vault_after,
+            harvested,
         });
-
         Ok(())
     }
+
+    /// Withdraw everything withheld on the mint to the vault, then distribute
+    /// the vault balance. Settles a sweep done with `harvest_only`.
+    /// `remaining_accounts` are the sink token accounts in split table order.
+    pub fn withdraw_and_distribute<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, HarvestAndDistribute<'info>>,
+        pool_ids: Vec<u8>,
+    ) -> Result<()> {
+        // Token-2022 only
+        require_keys_eq!(
+            ctx.accounts.token_program.key(),
+            spl_token_2022::ID,
+            ErrorCode::WrongTokenProgramForSink
+        );
+        let sinks = split_sink_accounts(
+            ctx.remaining_accounts,
+            &ctx.accounts.router,
+            &ctx.accounts.mint.key(),
+            &ctx.accounts.token_program.key(),
+        )?;
+        withdraw_and_settle(ctx.accounts, sinks, pool_ids, HarvestSummary::default(), WithdrawFrom::Mint)
+    }
 }
 
 // ------------------------------ Helpers --------------------------------------
 
-// ------------------------------ Helpers --------------------------------------
+/// Destination token accounts for the sinks: [stakers, treasury ATA, LP ATA].
+fn sink_destinations(sinks: &SinkConfig, mint: &Pubkey, token_program: &Pubkey) -> [Pubkey; 3] {
+    [
+        sinks.stakers_wallet,
+        get_associated_token_address_with_program_id(&sinks.treasury_owner, mint, token_program),
+        get_associated_token_address_with_program_id(&sinks.lp_owner, mint, token_program),
+    ]
+}
 
-fn compute_splits(amount: u64) -> Result<(u64, u64, u64)> {
-    let amount_u128 = amount as u128;
+/// Default 65 / 17.5 / 17.5 table for a freshly initialized router.
+fn default_splits(sinks: &SinkConfig, mint: &Pubkey, token_program: &Pubkey) -> Vec<SplitRecipient> {
+    let [stakers, treasury, lp] = sink_destinations(sinks, mint, token_program);
+    vec![
+        SplitRecipient {
+            recipient: stakers,
+            bps: STAKERS_BP,
+        },
+        SplitRecipient {
+            recipient: treasury,
+            bps: TREASURY_BP,
+        },
+        SplitRecipient {
+            recipient: lp,
+            bps: BPS_DENOMINATOR - STAKERS_BP - TREASURY_BP,
+        },
+    ]
+}
 
-    let stakers = amount_u128
-        .checked_mul(STAKERS_BP as u128)
-        .and_then(|v| v.checked_div(10_000))
-        .ok_or(RouterError::MathOverflow)?;
+fn validate_splits(splits: &[SplitRecipient]) -> Result<()> {
+    require!(!splits.is_empty(), RouterError::EmptySplitTable);
+    require!(splits.len() <= MAX_SPLIT_RECIPIENTS, RouterError::TooManyRecipients);
 
-    let treasury = amount_u128
-        .checked_mul(TREASURY_BP as u128)
-        .and_then(|v| v.checked_div(10_000))
-        .ok_or(RouterError::MathOverflow)?;
+    let mut total: u32 = 0;
+    for (i, split) in splits.iter().enumerate() {
+        require!(
+            splits[..i].iter().all(|s| s.recipient != split.recipient),
+            RouterError::DuplicateRecipient
+        );
+        total += split.bps as u32;
+    }
+    require!(total == BPS_DENOMINATOR as u32, RouterError::InvalidSplitTotal);
+    Ok(())
+}
 
-    let lp = amount_u128
-        .checked_sub(stakers)
-        .and_then(|v| v.checked_sub(treasury))
-        .ok_or(RouterError::MathOverflow)?;
+fn validate_crank_operators(crank_operators: &[Pubkey]) -> Result<()> {
+    require!(
+        crank_operators.len() <= MAX_CRANK_OPERATORS,
+        RouterError::TooManyCrankOperators
+    );
+    for (i, operator) in crank_operators.iter().enumerate() {
+        require_keys_neq!(*operator, Pubkey::default(), RouterError::InvalidCrankOperator);
+        require!(
+            !crank_operators[..i].contains(operator),
+            RouterError::DuplicateCrankOperator
+        );
+    }
+    Ok(())
+}
 
-    Ok((stakers as u64, treasury as u64, lp as u64))
+/// Net an entry of weight `bps` receives when `net_total` is split, rounded
+/// down. Rounding dust is not assigned to any entry.
+fn net_share(net_total: u64, bps: u16) -> u64 {
+    // Never exceeds `net_total`, so the cast is lossless
+    (net_total as u128 * bps as u128 / BPS_DENOMINATOR as u128) as u64
 }
 
 fn ceil_div_u128(n: u128, d: u128) -> u128 {
-    (n + d - 1) / d
+    n.div_ceil(d)
 }
 
-/// Compute a gross amount so the **net** is `target_net`.
+/// Token-2022 transfer fee on `gross`: `min(ceil(gross * bps / 10_000), max_fee)`.
+fn transfer_fee(gross: u64, bps: u16, max_fee: u64) -> u64 {
+    let fee = ceil_div_u128(gross as u128 * bps as u128, BPS_DENOMINATOR as u128);
+    // Bounded by `gross` since bps <= 10_000
+    (fee as u64).min(max_fee)
+}
+
+/// Smallest gross amount whose **net** after the transfer fee is `target_net`.
+///
+/// The net of `g` is `max(floor(g * (10_000 - bps) / 10_000), g - max_fee)`,
+/// so the minimum is the lesser of the uncapped and capped preimages. The net
+/// grows by at most one per unit of gross, so it lands on `target_net` exactly.
 fn gross_up(target_net: u64, bps: u16, max_fee: u64) -> Result<u64> {
     if target_net == 0 {
         return Ok(0);
     }
-    let rate_num: u128 = bps as u128;
-    let denom: u128 = 10_000;
+    let net_u = target_net as u128;
+    let denom = BPS_DENOMINATOR as u128;
+    let keep = denom.saturating_sub(bps as u128);
 
-    let net_u: u128 = target_net as u128;
-    let denom_minus = denom
-        .checked_sub(rate_num)
-        .ok_or(RouterError::MathOverflow)?;
-    require!(denom_minus > 0, RouterError::MathOverflow);
+    let capped = net_u + max_fee as u128;
+    let gross = if keep == 0 {
+        capped
+    } else {
+        ceil_div_u128(net_u * denom, keep).min(capped)
+    };
+    u64::try_from(gross).map_err(|_| RouterError::MathOverflow.into())
+}
 
-    let gross_uncapped_u =
-        ceil_div_u128(net_u.checked_mul(denom).ok_or(RouterError::MathOverflow)?, denom_minus);
-    let fee_uncapped_u = gross_uncapped_u
-        .checked_mul(rate_num)
-        .ok_or(RouterError::MathOverflow)?
-        / denom;
+/// Gross needed for every entry to net its share of `net_total`, or `None`
+/// if it does not fit in a u64.
+fn gross_total(net_total: u64, splits: &[SplitRecipient], bps: u16, max_fee: u64) -> Option<u64> {
+    splits.iter().try_fold(0u64, |acc, split| {
+        acc.checked_add(gross_up(net_share(net_total, split.bps), bps, max_fee).ok()?)
+    })
+}
+
+/// Closed-form estimate of `solve_net_total`, ignoring rounding.
+///
+/// An entry's gross is the lesser of its uncapped (`net * 10_000 / keep`)
+/// and capped (`net + max_fee`) preimage, so the gross total is the least of
+/// the linear totals over every choice of capped entries. With `j` entries
+/// capped, the heaviest `j` give the least one. The estimate is the largest
+/// net total any of those `n + 1` totals fits in `available`.
+fn estimate_net_total(available: u64, splits: &[SplitRecipient], bps: u16, max_fee: u64) -> u64 {
+    let denom = BPS_DENOMINATOR as u128;
+    let keep = denom.saturating_sub(bps as u128);
+    let mut weights: Vec<u128> = splits.iter().filter(|s| s.bps > 0).map(|s| s.bps as u128).collect();
+    weights.sort_unstable_by(|a, b| b.cmp(a));
+    let total: u128 = weights.iter().sum();
+
+    let (mut capped, mut best) = (0u128, 0u128);
+    for j in 0..=weights.len() {
+        if j > 0 {
+            capped += weights[j - 1];
+        }
+        let Some(budget) = (available as u128).checked_sub(j as u128 * max_fee as u128) else {
+            break;
+        };
+        let uncapped = total - capped;
+        // Net total where this choice's gross total reaches `budget + j * max_fee`
+        let net_total = if keep > 0 {
+            budget * denom * keep / (uncapped * denom + capped * keep)
+        } else if uncapped == 0 && capped > 0 {
+            budget * denom / capped
+        } else {
+            // Uncapped entries cannot be paid at a 100% fee
+            continue;
+        };
+        best = best.max(net_total);
+    }
+    best.min(u64::MAX as u128) as u64
+}
 
-    if fee_uncapped_u <= (max_fee as u128) {
-        let gross_u64 = u64::try_from(gross_uncapped_u).map_err(|_| RouterError::MathOverflow)?;
-        return Ok(gross_u64);
+/// Largest net total whose legs, grossed up for the transfer fee, fit in
+/// `available`.
+///
+/// Per-leg gross is monotonic in the net total. Starting from
+/// `estimate_net_total`, which rounding puts within about one token per leg
+/// of the answer for ordinary fees, the search doubles its step until it
+/// brackets the answer and then bisects the bracket. That is a handful of
+/// `gross_total` evaluations instead of one per bit of `available`.
+fn solve_net_total(available: u64, splits: &[SplitRecipient], bps: u16, max_fee: u64) -> u64 {
+    let fits = |net_total: u64| gross_total(net_total, splits, bps, max_fee).is_some_and(|g| g <= available);
+    // Flooring loses under one token per leg, so the answer is below `available + n`
+    let limit = available.saturating_add(splits.len() as u64);
+    let guess = estimate_net_total(available, splits, bps, max_fee).min(limit);
+
+    // Bracket the answer in [lo, hi], keeping `fits(lo)`; a zero net total always fits
+    let guess_fits = fits(guess);
+    let (mut lo, mut hi) = if guess_fits { (guess, limit) } else { (0, guess - 1) };
+    let mut step = 1u64;
+    if guess_fits {
+        while lo < hi {
+            let probe = lo.saturating_add(step).min(hi);
+            if !fits(probe) {
+                hi = probe - 1;
+                break;
+            }
+            lo = probe;
+            step = step.saturating_mul(2);
+        }
+    } else {
+        loop {
+            let probe = hi.saturating_sub(step);
+            if fits(probe) {
+                lo = probe;
+                break;
+            }
+            hi = probe - 1;
+            step = step.saturating_mul(2);
+        }
     }
 
-    let gross_capped_u = net_u
-        .checked_add(max_fee as u128)
-        .ok_or(RouterError::MathOverflow)?;
-    let gross_capped = u64::try_from(gross_capped_u).map_err(|_| RouterError::MathOverflow)?;
-    Ok(gross_capped)
+    while lo < hi {
+        let mid = lo + (hi - lo).div_ceil(2);
+        if fits(mid) {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    lo
 }
 
-fn maybe_gross_up_splits(
-    stakers: u64,
-    treasury: u64,
-    lp: u64,
+/// Per-entry gross, fee and net for distributing up to `available`.
+fn solve_legs(
+    available: u64,
+    splits: &[SplitRecipient],
     fee_params: Option<(u16, u64)>,
-) -> Result<(u64, u64, u64)> {
-    if let Some((bps, max_fee)) = fee_params {
-        // Skip gross-up if fee is >= 100% (mathematically impossible)
-        if bps >= 10000 {
-            return Ok((stakers, treasury, lp));
+) -> Result<Vec<DistributionLeg>> {
+    let (bps, max_fee) = fee_params.unwrap_or((0, 0));
+    let net_total = solve_net_total(available, splits, bps, max_fee);
+    splits
+        .iter()
+        .map(|split| {
+            let net = net_share(net_total, split.bps);
+            let gross = gross_up(net, bps, max_fee)?;
+            Ok(DistributionLeg {
+                recipient: split.recipient,
+                gross,
+                fee: transfer_fee(gross, bps, max_fee),
+                net,
+            })
+        })
+        .collect()
+}
+
+/// What the harvest step of a run did, for its `HarvestRun` event.
+#[derive(Default)]
+struct HarvestSummary {
+    sources: u32,
+    empty_sources: u32,
+    withheld_total: u64,
+    harvested: u64,
+    sinks_harvested: bool,
+    from_accounts: bool,
+    skipped: Vec<SkippedSource>,
+}
+
+/// Where a run's withdraw into the vault pulls withheld fees from.
+enum WithdrawFrom<'a, 'info> {
+    /// Everything withheld on the mint, after harvesting to it.
+    Mint,
+    /// Straight from these token accounts; the mint is not touched.
+    Accounts(&'a [AccountInfo<'info>]),
+}
+
+/// Validate the sources of a run and pick the accounts with fees withheld:
+/// the sinks first when `harvest_sinks` is set (picking up fees withheld by
+/// earlier payouts), then each valid source. Lenient mode drops invalid
+/// sources; otherwise the first one fails the run. Valid accounts with
+/// nothing withheld are left out of the CPI.
+fn collect_sources<'info>(
+    router: &Router,
+    sinks: &[AccountInfo<'info>],
+    sources: &[AccountInfo<'info>],
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Result<(Vec<AccountInfo<'info>>, HarvestSummary)> {
+    let mut list = Vec::with_capacity(sources.len() + sinks.len());
+    let mut summary = HarvestSummary {
+        sinks_harvested: router.harvest_sinks,
+        ..HarvestSummary::default()
+    };
+
+    // Sinks were already validated against the split table
+    if router.harvest_sinks {
+        for acc in sinks.iter() {
+            let withheld = check_source(acc, mint, token_program).map_err(skip_reason_error)?;
+            if withheld > 0 {
+                list.push(acc.clone());
+                summary.withheld_total = summary.withheld_total.saturating_add(withheld);
+            }
+        }
+    }
+
+    for acc in sources.iter() {
+        match check_source(acc, mint, token_program) {
+            Ok(0) => summary.empty_sources += 1,
+            Ok(withheld) => {
+                list.push(acc.clone());
+                summary.sources += 1;
+                summary.withheld_total = summary.withheld_total.saturating_add(withheld);
+            }
+            Err(reason) if router.lenient_sources => {
+                msg!("Skipping source {}: {:?}", acc.key(), reason);
+                summary.skipped.push(SkippedSource {
+                    account: acc.key(),
+                    reason,
+                });
+            }
+            Err(reason) => return Err(skip_reason_error(reason)),
         }
-        let s = gross_up(stakers, bps, max_fee)?;
-        let t = gross_up(treasury, bps, max_fee)?;
-        let l = gross_up(lp, bps, max_fee)?;
-        Ok((s, t, l))
+    }
+    Ok((list, summary))
+}
+
+/// Classify a harvest source: for a Token-2022 account of `mint`, its
+/// withheld transfer fees (0 without a TransferFeeAmount extension), or why
+/// it cannot be harvested.
+fn check_source(
+    acc: &AccountInfo,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> std::result::Result<u64, SkipReason> {
+    if acc.lamports() == 0 || acc.data_is_empty() {
+        return Err(SkipReason::Closed);
+    }
+    if acc.owner != token_program {
+        return Err(SkipReason::NotToken2022);
+    }
+    let data = acc.try_borrow_data().map_err(|_| SkipReason::NotTokenAccount)?;
+    let state = StateWithExtensions::<SplAccount>::unpack(&data).map_err(|_| SkipReason::NotTokenAccount)?;
+    if state.base.mint != *mint {
+        return Err(SkipReason::WrongMint);
+    }
+    Ok(state
+        .get_extension::<TransferFeeAmount>()
+        .map_or(0, |fee| fee.withheld_amount.into()))
+}
+
+/// Strict-mode error for an invalid harvest source.
+fn skip_reason_error(reason: SkipReason) -> Error {
+    match reason {
+        SkipReason::Closed | SkipReason::NotToken2022 => ErrorCode::WrongTokenProgramForSink.into(),
+        SkipReason::NotTokenAccount => anchor_lang::error::ErrorCode::AccountDidNotDeserialize.into(),
+        SkipReason::WrongMint => ErrorCode::InvalidMintForSink.into(),
+    }
+}
+
+/// Harvest withheld fees from `accounts` to the mint (permissionless in
+/// Token-2022). Returns the increase in the mint's withheld amount.
+fn harvest_to_mint<'a, 'info: 'a>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    accounts: impl Iterator<Item = &'a AccountInfo<'info>> + Clone,
+) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    let withheld_before = get_mint_withheld(&mint_info)?;
+
+    let mut ix = token2022_ix::harvest_withheld_tokens_to_mint(&token_program.key(), &mint.key(), &[])?;
+    ix.accounts
+        .extend(accounts.clone().map(|acc| AccountMeta::new(acc.key(), false)));
+    // Account infos: [mint, fee_accounts...]
+    let mut infos = vec![mint_info.clone()];
+    infos.extend(accounts.cloned());
+    invoke(&ix, &infos)?;
+
+    Ok(get_mint_withheld(&mint_info)?.saturating_sub(withheld_before))
+}
+
+/// Withdraw withheld fees to the vault, distribute the vault balance, sync
+/// the rewards program and book the run. Shared tail of
+/// `harvest_and_distribute`, `withdraw_from_accounts_and_distribute` and
+/// `withdraw_and_distribute`.
+fn withdraw_and_settle<'info>(
+    accounts: &mut HarvestAndDistribute<'info>,
+    sinks: &[AccountInfo<'info>],
+    pool_ids: Vec<u8>,
+    summary: HarvestSummary,
+    from: WithdrawFrom<'_, 'info>,
+) -> Result<()> {
+    let mint_key = accounts.mint.key();
+    let seeds = [
+        SEED_NAMESPACE,
+        SEED_ROUTER,
+        mint_key.as_ref(),
+        &[accounts.router.bump],
+    ];
+    let signer = &[&seeds[..]];
+    let vault_before = accounts.router_vault.amount;
+
+    // 2) Withdraw withheld → router_vault (authority = router PDA)
+    // AccountInfos must match ix metas: [mint, destination (vault), authority (router), sources...]
+    let mut infos_withdraw = vec![
+        accounts.mint.to_account_info(),
+        accounts.router_vault.to_account_info(),
+        accounts.router.to_account_info(),
+    ];
+    match from {
+        WithdrawFrom::Mint => {
+            let ix_withdraw = token2022_ix::withdraw_withheld_tokens_from_mint(
+                &accounts.token_program.key(),
+                &accounts.mint.key(),
+                &accounts.router_vault.key(),
+                &accounts.router.key(),
+                &[],
+            )?;
+            invoke_signed(&ix_withdraw, &infos_withdraw, signer)?;
+        }
+        WithdrawFrom::Accounts([]) => msg!("No withheld fees on the provided accounts"),
+        WithdrawFrom::Accounts(sources) => {
+            let source_keys: Vec<Pubkey> = sources.iter().map(|acc| acc.key()).collect();
+            let ix_withdraw = token2022_ix::withdraw_withheld_tokens_from_accounts(
+                &accounts.token_program.key(),
+                &accounts.mint.key(),
+                &accounts.router_vault.key(),
+                &accounts.router.key(),
+                &[],
+                &source_keys.iter().collect::<Vec<_>>(),
+            )?;
+            infos_withdraw.extend(sources.iter().cloned());
+            invoke_signed(&ix_withdraw, &infos_withdraw, signer)?;
+        }
+    }
+
+    // 3) Re-read vault AFTER withdraw, then distribute that fresh balance
+    accounts.router_vault.reload()?;
+    let amount = accounts.router_vault.amount;
+    msg!("Vault balance after withdraw: {} lamports", amount);
+
+    let clock = Clock::get()?;
+    let stats = &mut accounts.router.stats;
+    stats.total_withdrawn = stats
+        .total_withdrawn
+        .saturating_add(amount.saturating_sub(vault_before));
+    stats.harvest_runs = stats.harvest_runs.saturating_add(1);
+    stats.last_run_slot = clock.slot;
+    stats.last_run_timestamp = clock.unix_timestamp;
+
+    let decimals_from_mint = accounts.mint.decimals;
+    let fee = applied_fee(&accounts.mint.to_account_info())?;
+
+    // Below the minimum the run still counts in the stats above, so it is
+    // still reported: `HarvestRun` with no legs and nothing distributed
+    let (legs, distributed) = if amount < MIN_DISTRIBUTE {
+        msg!("Vault balance {} < MIN_DISTRIBUTE {}, skipping distribution", amount, MIN_DISTRIBUTE);
+        (Vec::new(), 0)
     } else {
-        Ok((stakers, treasury, lp))
+        let legs = distribute_now(
+            &accounts.token_program,
+            &accounts.router,
+            &accounts.mint,
+            &accounts.router_vault,
+            sinks,
+            signer,
+            amount,
+            decimals_from_mint,
+            accounts.caller.key(),
+            fee,
+        )?;
+        (legs, amount)
+    };
+
+    let recycled_fee = accounts.router.record_payout(&legs)?;
+
+    // Re-read for `vault_after`
+    accounts.router_vault.reload()?;
+    let vault_after = accounts.router_vault.amount;
+
+    // CPI: Call rewards program's sync_vault_and_distribute
+    // This triggers automatic distribution of deposited rewards to active pools
+    let stakers_wallet_key = accounts.stakers_wallet.key();
+    let stakers_amount = legs
+        .iter()
+        .find(|leg| leg.recipient == stakers_wallet_key)
+        .map_or(0, |leg| leg.net);
+    let rewards_cpi_config = accounts.router.rewards_cpi;
+    let rewards_accounts = (
+        accounts.rewards_program.as_ref(),
+        accounts.rewards_config.as_ref(),
+        accounts.pool_registry.as_ref(),
+        accounts.vault_authority_pda.as_ref(),
+    );
+    if stakers_amount == 0 {
+        // nothing reached the rewards vault
+    } else if !rewards_cpi_config.enabled {
+        msg!("Rewards CPI disabled - skipping sync");
+    } else if let (Some(rewards_program), Some(rewards_config), Some(pool_registry), Some(vault_authority_pda)) =
+        rewards_accounts
+    {
+        msg!("Calling rewards program sync_vault_and_distribute via CPI");
+        // Rewards PDAs were checked against their derivations by the accounts constraints
+        rewards_cpi::sync_vault_and_distribute(
+            CpiContext::new(
+                rewards_program.to_account_info(),
+                rewards_cpi::SyncVaultAndDistribute {
+                    config: rewards_config.to_account_info(),
+                    pool_registry: pool_registry.to_account_info(),
+                    mint: accounts.mint.to_account_info(),
+                    vault_authority: vault_authority_pda.to_account_info(),
+                    vault: accounts.stakers_wallet.to_account_info(),
+                    token_program: accounts.token_program.to_account_info(),
+                    system_program: accounts.system_program.to_account_info(),
+                },
+            ),
+            rewards_cpi_config.discriminator,
+            rewards_cpi::SyncVaultAndDistributeArgs { pool_ids },
+        )?;
+        
+        msg!("✅ Rewards vault synced and distributed via CPI");
+    } else {
+        // Only the authority may turn the sync off; a caller cannot skip it
+        // by leaving the accounts out
+        return err!(RouterError::MissingRewardsAccounts);
+    }
+
+    emit!(HarvestRun {
+        mint: mint_key,
+        caller: accounts.caller.key(),
+        sources: summary.sources,
+        empty_sources: summary.empty_sources,
+        withheld_total: summary.withheld_total,
+        harvested: summary.harvested,
+        vault_before,
+        withdrawn: amount.saturating_sub(vault_before),
+        distributed,
+        vault_after,
+        fee,
+        legs,
+        sinks_harvested: summary.sinks_harvested,
+        from_accounts: summary.from_accounts,
+        recycled_fee,
+        recycled_fees_total: accounts.router.recycled_fees,
+        skipped: summary.skipped,
+    });
+
+    Ok(())
+}
+
+/// Validate the leading `remaining_accounts` against the split table and
+/// return them. Entry `i` must be the token account at `router.splits[i]`.
+fn split_sink_accounts<'c, 'info>(
+    remaining_accounts: &'c [AccountInfo<'info>],
+    router: &Router,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Result<&'c [AccountInfo<'info>]> {
+    let n = router.splits.len();
+    require!(remaining_accounts.len() >= n, RouterError::MissingSinkAccounts);
+    let sinks = &remaining_accounts[..n];
+
+    for (acc, split) in sinks.iter().zip(router.splits.iter()) {
+        require_keys_eq!(acc.key(), split.recipient, RouterError::SinkAccountMismatch);
+        require_keys_eq!(*acc.owner, *token_program, ErrorCode::WrongTokenProgramForSink);
+        let mut data: &[u8] = &acc.try_borrow_data()?;
+        let ta = TokenAccount::try_deserialize(&mut data)?;
+        require_keys_eq!(ta.mint, *mint, ErrorCode::InvalidMintForSink);
     }
+
+    Ok(sinks)
 }
 
+/// Pay up to `amount` out of the vault per the split table.
+///
+/// Legs are solved so every recipient's **net** after the Token-2022 fee is
+/// its bps share of the largest net total that fits in `amount`. Whatever is
+/// left (rounding and fee steps) stays in the vault and is reported as the
+/// event's `remainder`. The returned legs carry each recipient's measured
+/// balance increase as `net`, not the solved target.
+#[allow(clippy::too_many_arguments)]
 fn distribute_now<'info>(
     token_program: &Interface<'info, TokenInterface>,
     router: &Account<'info, Router>,
     mint: &InterfaceAccount<'info, Mint>,
     router_vault: &InterfaceAccount<'info, TokenAccount>,
-    stakers_wallet: &InterfaceAccount<'info, TokenAccount>,
-    treasury_wallet: &InterfaceAccount<'info, TokenAccount>,
-    lp_pool_wallet: &InterfaceAccount<'info, TokenAccount>,
+    sinks: &[AccountInfo<'info>],
     signer: &[&[&[u8]]],
     amount: u64,
     decimals: u8,
-    fee_params: Option<(u16, u64)>,
-) -> Result<()> {
-    let (stakers_target, treasury_target, lp_target) = compute_splits(amount)?;
-    let (mut s_amt, mut t_amt, mut l_amt) =
-        maybe_gross_up_splits(stakers_target, treasury_target, lp_target, fee_params)?;
-
-    // Safety: if gross-up exceeds available amount, fall back to raw targets
-    let total_gross = s_amt
-        .checked_add(t_amt)
-        .ok_or(RouterError::MathOverflow)?
-        .checked_add(l_amt)
-        .ok_or(RouterError::MathOverflow)?;
-    if total_gross > amount {
-        s_amt = stakers_target;
-        t_amt = treasury_target;
-        l_amt = lp_target;
-    }
-
-    // Stakers
-    transfer_checked(
-        CpiContext::new_with_signer(
-            token_program.to_account_info(),
-            TransferChecked {
-                from: router_vault.to_account_info(),
-                mint: mint.to_account_info(),
-                to: stakers_wallet.to_account_info(),
-                authority: router.to_account_info(),
-            },
-            signer,
-        ),
-        s_amt,
-        decimals,
-    )?;
-
-    // Treasury
-    transfer_checked(
-        CpiContext::new_with_signer(
-            token_program.to_account_info(),
-            TransferChecked {
-                from: router_vault.to_account_info(),
-                mint: mint.to_account_info(),
-                to: treasury_wallet.to_account_info(),
-                authority: router.to_account_info(),
-            },
-            signer,
-        ),
-        t_amt,
-        decimals,
-    )?;
-
-    // LP
-    transfer_checked(
-        CpiContext::new_with_signer(
-            token_program.to_account_info(),
-            TransferChecked {
-                from: router_vault.to_account_info(),
-                mint: mint.to_account_info(),
-                to: lp_pool_wallet.to_account_info(),
-                authority: router.to_account_info(),
-            },
-            signer,
-        ),
-        l_amt,
-        decimals,
-    )?;
+    caller: Pubkey,
+    fee: AppliedFee,
+) -> Result<Vec<DistributionLeg>> {
+    let mut legs = solve_legs(amount, &router.splits, fee.params())?;
+
+    // Sinks were validated against the table by `split_sink_accounts`
+    for (sink, leg) in sinks.iter().zip(legs.iter_mut()) {
+        if leg.gross == 0 {
+            continue;
+        }
+        let before = token_amount(sink)?;
+        transfer_checked(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                TransferChecked {
+                    from: router_vault.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: sink.clone(),
+                    authority: router.to_account_info(),
+                },
+                signer,
+            ),
+            leg.gross,
+            decimals,
+        )?;
+        leg.net = token_amount(sink)?.saturating_sub(before);
+        leg.fee = leg.gross.saturating_sub(leg.net);
+    }
 
+    let distributed = legs.iter().map(|leg| leg.gross).sum::<u64>();
     emit!(FeeDistribution {
-        stakers_amount: stakers_target, // report intended net targets
-        treasury_amount: treasury_target,
-        lp_amount: lp_target,
+        mint: mint.key(),
+        caller,
+        fee,
+        legs: legs.clone(),
         total: amount,
+        remainder: amount - distributed,
     });
 
-    Ok(())
+    Ok(legs)
 }
 
 /// Read Token-2022 transfer-fee parameters for the current epoch.
@@ -710,3 +2040,348 @@ fn get_fee_params(mint_ai: &AccountInfo) -> Result<Option<(u16, u64)>> {
         epoch_fee.maximum_fee.into(),
     )))
 }
+
+/// The mint's transfer fee for the current epoch, as applied to a payout.
+fn applied_fee(mint_ai: &AccountInfo) -> Result<AppliedFee> {
+    let epoch = Clock::get()?.epoch;
+    Ok(match get_fee_params(mint_ai)? {
+        Some((transfer_fee_basis_points, maximum_fee)) => AppliedFee {
+            epoch,
+            transfer_fee_basis_points,
+            maximum_fee,
+            fallback: false,
+        },
+        None => AppliedFee {
+            epoch,
+            fallback: true,
+            ..AppliedFee::default()
+        },
+    })
+}
+
+/// Token amount of a Token-2022 token account.
+fn token_amount(acc: &AccountInfo) -> Result<u64> {
+    let data = acc.try_borrow_data()?;
+    let state = StateWithExtensions::<SplAccount>::unpack(&data)
+        .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize)?;
+    Ok(state.base.amount)
+}
+
+/// Withheld fees currently held on the mint (0 without a TransferFeeConfig).
+fn get_mint_withheld(mint_ai: &AccountInfo) -> Result<u64> {
+    let data = mint_ai.try_borrow_data()?;
+    let Ok(state) = StateWithExtensions::<SplMint>::unpack(&data) else {
+        return Ok(0);
+    };
+    Ok(state
+        .get_extension::<TransferFeeConfig>()
+        .map_or(0, |cfg| cfg.withheld_amount.into()))
+}
+
+/// Read the TransferFeeConfig withdraw authority (`None` if unset).
+fn get_withdraw_withheld_authority(mint_ai: &AccountInfo) -> Result<Option<Pubkey>> {
+    let data = mint_ai.try_borrow_data()?;
+    let state = StateWithExtensions::<SplMint>::unpack(&data)
+        .map_err(|_| RouterError::MissingTransferFeeConfig)?;
+    let cfg = state
+        .get_extension::<TransferFeeConfig>()
+        .map_err(|_| RouterError::MissingTransferFeeConfig)?;
+    Ok(Option::<Pubkey>::from(cfg.withdraw_withheld_authority))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use spl_token_2022::extension::transfer_fee::TransferFee;
+
+    /// Fee Token-2022 charges on a transfer of `amount`.
+    fn token_fee(amount: u64, bps: u16, max_fee: u64) -> u64 {
+        TransferFee {
+            epoch: 0.into(),
+            maximum_fee: max_fee.into(),
+            transfer_fee_basis_points: bps.into(),
+        }
+        .calculate_fee(amount)
+        .unwrap()
+    }
+
+    /// Amounts biased toward the edges where rounding and overflow bite.
+    fn amount() -> impl Strategy<Value = u64> {
+        prop_oneof![0..=20_000u64, any::<u64>(), (u64::MAX - 20_000)..=u64::MAX]
+    }
+
+    fn max_fee() -> impl Strategy<Value = u64> {
+        prop_oneof![0..=1_000u64, any::<u64>(), Just(u64::MAX)]
+    }
+
+    /// 1..=MAX_SPLIT_RECIPIENTS weights summing to BPS_DENOMINATOR.
+    fn split_table() -> impl Strategy<Value = Vec<SplitRecipient>> {
+        prop::collection::vec(0..=BPS_DENOMINATOR, 0..MAX_SPLIT_RECIPIENTS).prop_map(|mut cuts| {
+            cuts.push(0);
+            cuts.push(BPS_DENOMINATOR);
+            cuts.sort_unstable();
+            cuts.windows(2)
+                .map(|w| SplitRecipient {
+                    recipient: Pubkey::new_unique(),
+                    bps: w[1] - w[0],
+                })
+                .collect()
+        })
+    }
+
+    fn leg(recipient: Pubkey, gross: u64) -> DistributionLeg {
+        DistributionLeg {
+            recipient,
+            gross,
+            fee: 1,
+            net: gross - 1,
+        }
+    }
+
+    #[test]
+    fn ledger_evicts_retired_sinks_into_retired_totals() {
+        let mut stats = RouterStats::default();
+        let old: Vec<Pubkey> = (0..MAX_SINK_TOTALS).map(|_| Pubkey::new_unique()).collect();
+        let legs: Vec<_> = old.iter().map(|&key| leg(key, 10)).collect();
+        stats.record_payout(&legs, &[]);
+        assert_eq!(stats.sinks.len(), MAX_SINK_TOTALS);
+
+        // The first entry is still in the table, the second is not
+        let table = [SplitRecipient {
+            recipient: old[0],
+            bps: BPS_DENOMINATOR,
+        }];
+        let new = Pubkey::new_unique();
+        stats.record_payout(&[leg(new, 20)], &table);
+
+        assert_eq!(stats.sinks.len(), MAX_SINK_TOTALS);
+        assert_eq!(stats.sinks[0].recipient, old[0]);
+        assert_eq!(stats.sinks[1], SinkTotal { recipient: new, gross: 20, net: 19 });
+        assert_eq!((stats.retired.gross, stats.retired.net), (10, 9));
+        assert_eq!(stats.total_distributed, 10 * MAX_SINK_TOTALS as u64 + 20);
+    }
+
+    /// Classify a synthetic account with the given owner, lamports and data.
+    fn classify(owner: Pubkey, lamports: u64, mut data: Vec<u8>, mint: &Pubkey) -> std::result::Result<u64, SkipReason> {
+        let key = Pubkey::new_unique();
+        let mut lamports = lamports;
+        let acc = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        check_source(&acc, mint, &spl_token_2022::ID)
+    }
+
+    fn token_account_data(mint: Pubkey) -> Vec<u8> {
+        use anchor_lang::solana_program::program_pack::Pack;
+        let mut data = vec![0; SplAccount::LEN];
+        SplAccount {
+            mint,
+            owner: Pubkey::new_unique(),
+            state: spl_token_2022::state::AccountState::Initialized,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+        data
+    }
+
+    /// Token account for `mint` with a TransferFeeAmount extension.
+    fn fee_account_data(mint: Pubkey, withheld: u64) -> Vec<u8> {
+        use spl_token_2022::extension::{BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut};
+        let len = ExtensionType::try_calculate_account_len::<SplAccount>(&[ExtensionType::TransferFeeAmount])
+            .unwrap();
+        let mut data = vec![0; len];
+        let mut state = StateWithExtensionsMut::<SplAccount>::unpack_uninitialized(&mut data).unwrap();
+        state.init_extension::<TransferFeeAmount>(true).unwrap().withheld_amount = withheld.into();
+        state.base = SplAccount {
+            mint,
+            owner: Pubkey::new_unique(),
+            state: spl_token_2022::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+        data
+    }
+
+    #[test]
+    fn check_source_classifies_accounts() {
+        let mint = Pubkey::new_unique();
+        let token_2022 = spl_token_2022::ID;
+        assert_eq!(classify(token_2022, 1, token_account_data(mint), &mint), Ok(0));
+        assert_eq!(classify(token_2022, 1, fee_account_data(mint, 0), &mint), Ok(0));
+        assert_eq!(classify(token_2022, 1, fee_account_data(mint, 42), &mint), Ok(42));
+        assert_eq!(
+            classify(token_2022, 0, token_account_data(mint), &mint),
+            Err(SkipReason::Closed)
+        );
+        assert_eq!(
+            classify(Pubkey::default(), 1, vec![], &mint),
+            Err(SkipReason::Closed)
+        );
+        assert_eq!(
+            classify(Pubkey::new_unique(), 1, token_account_data(mint), &mint),
+            Err(SkipReason::NotToken2022)
+        );
+        assert_eq!(
+            classify(token_2022, 1, vec![1; 82], &mint),
+            Err(SkipReason::NotTokenAccount)
+        );
+        assert_eq!(
+            classify(token_2022, 1, token_account_data(Pubkey::new_unique()), &mint),
+            Err(SkipReason::WrongMint)
+        );
+    }
+
+    #[test]
+    fn fallback_fee_solves_without_a_fee() {
+        let splits = [SplitRecipient {
+            recipient: Pubkey::new_unique(),
+            bps: BPS_DENOMINATOR,
+        }];
+        let fallback = AppliedFee {
+            epoch: 7,
+            fallback: true,
+            ..AppliedFee::default()
+        };
+        assert_eq!(fallback.params(), None);
+        assert_eq!(solve_legs(1_000, &splits, fallback.params()).unwrap()[0].gross, 1_000);
+
+        let fee = AppliedFee {
+            epoch: 7,
+            transfer_fee_basis_points: 100,
+            maximum_fee: 5,
+            fallback: false,
+        };
+        assert_eq!(fee.params(), Some((100, 5)));
+        let leg = solve_legs(1_000, &splits, fee.params()).unwrap()[0];
+        assert_eq!((leg.gross, leg.fee, leg.net), (1_000, 5, 995));
+    }
+
+    fn split(bps: u16) -> SplitRecipient {
+        SplitRecipient {
+            recipient: Pubkey::new_unique(),
+            bps,
+        }
+    }
+
+    #[test]
+    fn validate_splits_rejects_bad_tables() {
+        let full = || {
+            let lp = BPS_DENOMINATOR - STAKERS_BP - TREASURY_BP;
+            vec![split(STAKERS_BP), split(TREASURY_BP), split(lp)]
+        };
+        assert!(validate_splits(&full()).is_ok());
+        assert_eq!(validate_splits(&[]).unwrap_err(), RouterError::EmptySplitTable.into());
+
+        // MAX_SPLIT_RECIPIENTS entries fit, one more does not
+        let mut table: Vec<_> = (0..MAX_SPLIT_RECIPIENTS).map(|_| split(1_250)).collect();
+        assert!(validate_splits(&table).is_ok());
+        table.push(split(0));
+        assert_eq!(validate_splits(&table).unwrap_err(), RouterError::TooManyRecipients.into());
+
+        let mut table = full();
+        table[2].recipient = table[0].recipient;
+        assert_eq!(validate_splits(&table).unwrap_err(), RouterError::DuplicateRecipient.into());
+
+        let mut table = full();
+        table[2].bps -= 1;
+        assert_eq!(validate_splits(&table).unwrap_err(), RouterError::InvalidSplitTotal.into());
+        table[2].bps += 2;
+        assert_eq!(validate_splits(&table).unwrap_err(), RouterError::InvalidSplitTotal.into());
+    }
+
+    proptest! {
+        #[test]
+        fn transfer_fee_matches_token_2022(
+            gross in amount(),
+            bps in 0..=BPS_DENOMINATOR,
+            max_fee in max_fee(),
+        ) {
+            prop_assert_eq!(transfer_fee(gross, bps, max_fee), token_fee(gross, bps, max_fee));
+        }
+
+        #[test]
+        fn gross_up_is_minimal_and_exact(
+            target in amount(),
+            bps in 0..=BPS_DENOMINATOR,
+            max_fee in max_fee(),
+        ) {
+            match gross_up(target, bps, max_fee) {
+                Ok(gross) => {
+                    prop_assert_eq!(gross - token_fee(gross, bps, max_fee), target);
+                    if gross > 0 {
+                        let less = gross - 1;
+                        prop_assert!(less - token_fee(less, bps, max_fee) < target);
+                    }
+                }
+                Err(_) => {
+                    // Only when neither the capped nor the uncapped gross fits in a u64
+                    prop_assert!(target as u128 + max_fee as u128 > u64::MAX as u128);
+                    prop_assert!(
+                        bps == BPS_DENOMINATOR
+                            || (target as u128 * 10_000).div_ceil(10_000 - bps as u128)
+                                > u64::MAX as u128
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn solved_legs_fit_and_net_exact_shares(
+            available in amount(),
+            splits in split_table(),
+            bps in 0..=BPS_DENOMINATOR,
+            max_fee in max_fee(),
+        ) {
+            let net_total = solve_net_total(available, &splits, bps, max_fee);
+            let legs = solve_legs(available, &splits, Some((bps, max_fee))).unwrap();
+            prop_assert_eq!(legs.len(), splits.len());
+
+            let mut gross_sum = 0u128;
+            for (leg, split) in legs.iter().zip(splits.iter()) {
+                prop_assert_eq!(leg.recipient, split.recipient);
+                prop_assert_eq!(leg.fee, token_fee(leg.gross, bps, max_fee));
+                prop_assert_eq!(leg.net, leg.gross - leg.fee);
+                prop_assert_eq!(
+                    leg.net as u128,
+                    net_total as u128 * split.bps as u128 / BPS_DENOMINATOR as u128
+                );
+                gross_sum += leg.gross as u128;
+            }
+            prop_assert!(gross_sum <= available as u128);
+
+            // One more token of net would not fit
+            let next = gross_total(net_total + 1, &splits, bps, max_fee);
+            prop_assert!(next.is_none_or(|g| g > available));
+        }
+
+        #[test]
+        fn estimate_is_close_for_ordinary_fees(
+            available in amount(),
+            splits in split_table(),
+            bps in 0..=5_000u16,
+            max_fee in max_fee(),
+        ) {
+            // Rounding moves each leg's gross by at most 10_000 / keep <= 2
+            // tokens, and the gross total grows at least one per unit of net
+            let n = splits.len() as u64;
+            let estimate = estimate_net_total(available, &splits, bps, max_fee);
+            let gap = solve_net_total(available, &splits, bps, max_fee).abs_diff(estimate);
+            prop_assert!(gap <= 2 * n + 1, "gap {} for {} legs", gap, n);
+        }
+
+        #[test]
+        fn solved_legs_without_fee_config_are_plain_shares(
+            available in amount(),
+            splits in split_table(),
+        ) {
+            let legs = solve_legs(available, &splits, None).unwrap();
+            for leg in legs.iter() {
+                prop_assert_eq!(leg.fee, 0);
+                prop_assert_eq!(leg.gross, leg.net);
+            }
+            // Only flooring dust stays behind: under one token per leg
+            let distributed: u128 = legs.iter().map(|leg| leg.gross as u128).sum();
+            prop_assert!(available as u128 - distributed < splits.len() as u128);
+        }
+    }
+}
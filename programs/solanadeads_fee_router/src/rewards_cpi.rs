@@ -0,0 +1,85 @@
+// programs/solanadeads_fee_router/src/rewards_cpi.rs
+
+//! Typed CPI client for the rewards program's `sync_vault_and_distribute`.
+//!
+//! Mirrors the `anchor_spl` helpers: build a `CpiContext` over
+//! [`SyncVaultAndDistribute`] and call [`sync_vault_and_distribute`]. The
+//! discriminator is passed in because it is configured per router.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+/// Accounts in the order the rewards program expects them.
+#[derive(Clone)]
+pub struct SyncVaultAndDistribute<'info> {
+    pub config: AccountInfo<'info>,
+    pub pool_registry: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub vault_authority: AccountInfo<'info>,
+    pub vault: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+impl ToAccountMetas for SyncVaultAndDistribute<'_> {
+    fn to_account_metas(&self, _is_signer: Option<bool>) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.config.key(), false),
+            AccountMeta::new_readonly(self.pool_registry.key(), false),
+            AccountMeta::new_readonly(self.mint.key(), false),
+            AccountMeta::new_readonly(self.vault_authority.key(), false),
+            AccountMeta::new(self.vault.key(), false),
+            AccountMeta::new_readonly(self.token_program.key(), false),
+            AccountMeta::new_readonly(self.system_program.key(), false),
+        ]
+    }
+}
+
+impl<'info> ToAccountInfos<'info> for SyncVaultAndDistribute<'info> {
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.config.clone(),
+            self.pool_registry.clone(),
+            self.mint.clone(),
+            self.vault_authority.clone(),
+            self.vault.clone(),
+            self.token_program.clone(),
+            self.system_program.clone(),
+        ]
+    }
+}
+
+/// Instruction arguments. An empty `pool_ids` lets the rewards program use
+/// its own active pool list.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyncVaultAndDistributeArgs {
+    pub pool_ids: Vec<u8>,
+}
+
+/// Build the raw instruction: discriminator followed by the Borsh-encoded args.
+pub fn instruction(
+    program_id: Pubkey,
+    discriminator: [u8; 8],
+    accounts: &SyncVaultAndDistribute,
+    args: &SyncVaultAndDistributeArgs,
+) -> Result<Instruction> {
+    let mut data = discriminator.to_vec();
+    args.serialize(&mut data)?;
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(None),
+        data,
+    })
+}
+
+pub fn sync_vault_and_distribute<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, SyncVaultAndDistribute<'info>>,
+    discriminator: [u8; 8],
+    args: SyncVaultAndDistributeArgs,
+) -> Result<()> {
+    let ix = instruction(ctx.program.key(), discriminator, &ctx.accounts, &args)?;
+    let mut infos = ctx.accounts.to_account_infos();
+    infos.push(ctx.program);
+    invoke_signed(&ix, &infos, ctx.signer_seeds).map_err(Into::into)
+}
@@ -0,0 +1,140 @@
+// programs/solanadeads_fee_router/tests/authority.rs
+
+//! Two-step authority handover: `propose_authority` by the current authority,
+//! `accept_authority` signed by the pending key, and `renounce_authority`,
+//! after which nothing authority-gated can run.
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solanadeads_fee_router::{accounts, instruction, RouterError};
+use solanadeads_fee_router_test_support::{assert_custom_error, Fixture};
+
+fn propose_ix(fx: &Fixture, authority: Pubkey, new_authority: Pubkey) -> Instruction {
+    fx.admin_ix(
+        accounts::ProposeAuthority {
+            router: fx.router,
+            authority,
+            mint: fx.mint,
+        },
+        instruction::ProposeAuthority { new_authority },
+    )
+}
+
+fn accept_ix(fx: &Fixture, new_authority: Pubkey) -> Instruction {
+    fx.admin_ix(
+        accounts::AcceptAuthority {
+            router: fx.router,
+            new_authority,
+            mint: fx.mint,
+        },
+        instruction::AcceptAuthority {},
+    )
+}
+
+fn set_guardian_ix(fx: &Fixture, authority: Pubkey) -> Instruction {
+    fx.admin_ix(
+        accounts::SetGuardian {
+            router: fx.router,
+            authority,
+            mint: fx.mint,
+        },
+        instruction::SetGuardian {
+            guardian: Pubkey::new_unique(),
+        },
+    )
+}
+
+#[tokio::test]
+async fn pending_authority_accepts_handover() {
+    let mut fx = Fixture::new().await;
+    let old = fx.payer();
+    let new = Keypair::new();
+
+    let ix = propose_ix(&fx, old, new.pubkey());
+    fx.process(&[ix], &[]).await.unwrap();
+    let router = fx.router_state().await;
+    assert_eq!((router.authority, router.pending_authority), (old, Some(new.pubkey())));
+
+    let ix = accept_ix(&fx, new.pubkey());
+    fx.process(&[ix], &[&new]).await.unwrap();
+    let router = fx.router_state().await;
+    assert_eq!((router.authority, router.pending_authority), (new.pubkey(), None));
+
+    // The old key is out, the new one is in
+    let ix = set_guardian_ix(&fx, old);
+    assert_custom_error(fx.process(&[ix], &[]).await, AnchorErrorCode::ConstraintHasOne.into());
+    let ix = set_guardian_ix(&fx, new.pubkey());
+    fx.process(&[ix], &[&new]).await.unwrap();
+}
+
+#[tokio::test]
+async fn accept_rejects_other_signer() {
+    let mut fx = Fixture::new().await;
+    let pending = Keypair::new().pubkey();
+    let ix = propose_ix(&fx, fx.payer(), pending);
+    fx.process(&[ix], &[]).await.unwrap();
+
+    let impostor = Keypair::new();
+    let ix = accept_ix(&fx, impostor.pubkey());
+    assert_custom_error(
+        fx.process(&[ix], &[&impostor]).await,
+        RouterError::PendingAuthorityMismatch.into(),
+    );
+    let router = fx.router_state().await;
+    assert_eq!((router.authority, router.pending_authority), (fx.payer(), Some(pending)));
+}
+
+#[tokio::test]
+async fn accept_requires_pending_authority() {
+    let mut fx = Fixture::new().await;
+    let new = Keypair::new();
+    let ix = accept_ix(&fx, new.pubkey());
+    assert_custom_error(
+        fx.process(&[ix], &[&new]).await,
+        RouterError::NoPendingAuthority.into(),
+    );
+}
+
+#[tokio::test]
+async fn propose_rejects_default_key() {
+    let mut fx = Fixture::new().await;
+    let ix = propose_ix(&fx, fx.payer(), Pubkey::default());
+    assert_custom_error(fx.process(&[ix], &[]).await, RouterError::InvalidAuthority.into());
+    assert_eq!(fx.router_state().await.pending_authority, None);
+}
+
+#[tokio::test]
+async fn renounce_locks_authority_gated_instructions() {
+    let mut fx = Fixture::new().await;
+    let old = fx.payer();
+    // A pending handover does not survive the renounce
+    let pending = Keypair::new();
+    let ix = propose_ix(&fx, old, pending.pubkey());
+    fx.process(&[ix], &[]).await.unwrap();
+
+    let ix = fx.admin_ix(
+        accounts::RenounceAuthority {
+            router: fx.router,
+            authority: old,
+            mint: fx.mint,
+        },
+        instruction::RenounceAuthority {},
+    );
+    fx.process(&[ix], &[]).await.unwrap();
+    let router = fx.router_state().await;
+    assert_eq!((router.authority, router.pending_authority), (Pubkey::default(), None));
+
+    let ix = set_guardian_ix(&fx, old);
+    assert_custom_error(fx.process(&[ix], &[]).await, AnchorErrorCode::ConstraintHasOne.into());
+    let ix = fx.update_splits_ix(old, router.splits.clone());
+    assert_custom_error(fx.process(&[ix], &[]).await, AnchorErrorCode::ConstraintHasOne.into());
+    let ix = propose_ix(&fx, old, old);
+    assert_custom_error(fx.process(&[ix], &[]).await, AnchorErrorCode::ConstraintHasOne.into());
+    let ix = accept_ix(&fx, pending.pubkey());
+    assert_custom_error(
+        fx.process(&[ix], &[&pending]).await,
+        RouterError::NoPendingAuthority.into(),
+    );
+}
@@ -0,0 +1,130 @@
+// programs/solanadeads_fee_router/tests/crank_roles.rs
+
+//! Who may run the crank: the authority, listed crank operators, or anyone
+//! once permissionless mode is on. The operator list is bounded and holds
+//! distinct, non-default keys. Strangers are rejected in
+//! `harvest_and_distribute.rs`.
+
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solanadeads_fee_router::{accounts, instruction, RouterError, MAX_CRANK_OPERATORS};
+use solanadeads_fee_router_test_support::{assert_custom_error, Fixture};
+
+fn set_crank_operators_ix(fx: &Fixture, crank_operators: Vec<Pubkey>) -> Instruction {
+    fx.admin_ix(
+        accounts::SetCrankRoles {
+            router: fx.router,
+            authority: fx.payer(),
+            mint: fx.mint,
+        },
+        instruction::SetCrankOperators { crank_operators },
+    )
+}
+
+fn set_permissionless_ix(fx: &Fixture, permissionless: bool) -> Instruction {
+    fx.admin_ix(
+        accounts::SetCrankRoles {
+            router: fx.router,
+            authority: fx.payer(),
+            mint: fx.mint,
+        },
+        instruction::SetPermissionless { permissionless },
+    )
+}
+
+#[tokio::test]
+async fn crank_operator_can_crank() {
+    let mut fx = Fixture::new().await;
+    let operator = Keypair::new();
+    let ix = set_crank_operators_ix(&fx, vec![Pubkey::new_unique(), operator.pubkey()]);
+    fx.process(&[ix], &[]).await.unwrap();
+
+    let sources = fx.fee_bearing_accounts(1, 1_000_000).await;
+    let accounts = fx.harvest_accounts(operator.pubkey());
+    let ix = fx.harvest_ix(accounts, &sources);
+    fx.process(&[ix], &[&operator]).await.unwrap();
+    assert_eq!(fx.withheld(&sources[0]).await, 0);
+    assert_eq!(fx.router_state().await.stats.harvest_runs, 1);
+
+    let router_vault = fx.router_vault;
+    fx.mint_to(&router_vault, 10_000).await;
+    let ix = fx.distribute_ix(operator.pubkey(), 10_000);
+    fx.process(&[ix], &[&operator]).await.unwrap();
+    assert_eq!(fx.balance(&router_vault).await, 0);
+
+    // Dropped from the list, the operator is a stranger again
+    let ix = set_crank_operators_ix(&fx, Vec::new());
+    fx.process(&[ix], &[]).await.unwrap();
+    fx.mint_to(&router_vault, 10_000).await;
+    let ix = fx.distribute_ix(operator.pubkey(), 10_000);
+    assert_custom_error(
+        fx.process(&[ix], &[&operator]).await,
+        RouterError::UnauthorizedCaller.into(),
+    );
+}
+
+#[tokio::test]
+async fn permissionless_mode_opens_crank() {
+    let mut fx = Fixture::new().await;
+    let ix = set_permissionless_ix(&fx, true);
+    fx.process(&[ix], &[]).await.unwrap();
+    assert!(fx.router_state().await.permissionless);
+
+    let anyone = Keypair::new();
+    let sources = fx.fee_bearing_accounts(1, 1_000_000).await;
+    let accounts = fx.harvest_accounts(anyone.pubkey());
+    let ix = fx.harvest_ix(accounts, &sources);
+    fx.process(&[ix], &[&anyone]).await.unwrap();
+    assert_eq!(fx.withheld(&sources[0]).await, 0);
+
+    let router_vault = fx.router_vault;
+    fx.mint_to(&router_vault, 10_000).await;
+    let ix = fx.distribute_ix(anyone.pubkey(), 10_000);
+    fx.process(&[ix], &[&anyone]).await.unwrap();
+
+    let ix = set_permissionless_ix(&fx, false);
+    fx.process(&[ix], &[]).await.unwrap();
+    fx.mint_to(&router_vault, 10_000).await;
+    let ix = fx.distribute_ix(anyone.pubkey(), 10_000);
+    assert_custom_error(
+        fx.process(&[ix], &[&anyone]).await,
+        RouterError::UnauthorizedCaller.into(),
+    );
+}
+
+#[tokio::test]
+async fn crank_operator_list_is_bounded() {
+    let mut fx = Fixture::new().await;
+    let full: Vec<Pubkey> = (0..MAX_CRANK_OPERATORS).map(|_| Pubkey::new_unique()).collect();
+    let ix = set_crank_operators_ix(&fx, full.clone());
+    fx.process(&[ix], &[]).await.unwrap();
+    assert_eq!(fx.router_state().await.crank_operators, full);
+
+    let mut over = full.clone();
+    over.push(Pubkey::new_unique());
+    let ix = set_crank_operators_ix(&fx, over);
+    assert_custom_error(
+        fx.process(&[ix], &[]).await,
+        RouterError::TooManyCrankOperators.into(),
+    );
+    assert_eq!(fx.router_state().await.crank_operators, full);
+}
+
+#[tokio::test]
+async fn crank_operator_list_rejects_duplicates_and_default_key() {
+    let mut fx = Fixture::new().await;
+    let operator = Pubkey::new_unique();
+
+    let ix = set_crank_operators_ix(&fx, vec![operator, Pubkey::new_unique(), operator]);
+    assert_custom_error(
+        fx.process(&[ix], &[]).await,
+        RouterError::DuplicateCrankOperator.into(),
+    );
+    let ix = set_crank_operators_ix(&fx, vec![operator, Pubkey::default()]);
+    assert_custom_error(
+        fx.process(&[ix], &[]).await,
+        RouterError::InvalidCrankOperator.into(),
+    );
+    assert!(fx.router_state().await.crank_operators.is_empty());
+}
@@ -0,0 +1,275 @@
+// programs/solanadeads_fee_router/tests/harvest_and_distribute.rs
+
+//! End-to-end harvest: fee-bearing transfers withhold fees on holder accounts,
+//! `harvest_and_distribute` sweeps them into the router vault and pays the
+//! default 65 / 17.5 / 17.5 table. Balances are asserted to the unit.
+
+use solana_sdk::signature::{Keypair, Signer};
+use solanadeads_fee_router::RouterError;
+use solanadeads_fee_router_test_support::{assert_custom_error, expected_fee, Fixture};
+
+/// Three holders each receive 1_000_000 at 1% => 10_000 withheld apiece.
+const TRANSFER: u64 = 1_000_000;
+const SOURCES: usize = 3;
+
+#[tokio::test]
+async fn harvest_pays_sinks_exact_amounts() {
+    let mut fx = Fixture::new().await;
+    let sources = fx.fee_bearing_accounts(SOURCES, TRANSFER).await;
+    for source in &sources {
+        assert_eq!(fx.withheld(source).await, expected_fee(TRANSFER));
+    }
+
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix(accounts, &sources);
+    fx.process(&[ix], &[]).await.unwrap();
+
+    // 30_000 harvested. The largest net total that fits is 29_701: nets
+    // 19_305 / 5_197 / 5_197 gross up to 19_500 / 5_250 / 5_250, which uses
+    // the whole vault.
+    let harvested = expected_fee(TRANSFER) * SOURCES as u64;
+    assert_eq!(harvested, 30_000);
+    let (stakers, treasury, lp) = (19_500, 5_250, 5_250);
+    assert_eq!(stakers + treasury + lp, harvested);
+
+    let (router_vault, stakers_wallet, treasury_wallet, lp_wallet) = (
+        fx.router_vault,
+        fx.sinks.stakers_wallet,
+        fx.treasury_wallet,
+        fx.lp_wallet,
+    );
+    assert_eq!(fx.balance(&router_vault).await, 0);
+    assert_eq!(fx.balance(&stakers_wallet).await, 19_305);
+    assert_eq!(fx.balance(&treasury_wallet).await, 5_197);
+    assert_eq!(fx.balance(&lp_wallet).await, 5_197);
+    assert_eq!(fx.withheld(&stakers_wallet).await, expected_fee(stakers));
+    assert_eq!(fx.withheld(&treasury_wallet).await, expected_fee(treasury));
+    assert_eq!(fx.withheld(&lp_wallet).await, expected_fee(lp));
+
+    for source in &sources {
+        assert_eq!(fx.withheld(source).await, 0);
+    }
+    assert_eq!(fx.mint_withheld().await, 0);
+    assert_eq!(fx.rewards_sync_calls().await, 1);
+    assert_eq!(
+        fx.router_state().await.recycled_fees,
+        expected_fee(stakers) + expected_fee(treasury) + expected_fee(lp)
+    );
+}
+
+#[tokio::test]
+async fn harvest_sweeps_sinks_when_enabled() {
+    let mut fx = Fixture::new().await;
+    fx.set_harvest_sinks(true).await;
+    let sources = fx.fee_bearing_accounts(SOURCES, TRANSFER).await;
+
+    // First run pays 19_500 / 5_250 / 5_250, leaving 195 + 53 + 53 on the sinks
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix(accounts, &sources);
+    fx.process(&[ix], &[]).await.unwrap();
+    assert_eq!(fx.router_state().await.recycled_fees, 301);
+
+    // Second run has no sources: only the recycled 301 comes back, paid as
+    // 195 / 53 / 53 with 2 + 1 + 1 withheld again
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix(accounts, &[]);
+    fx.process(&[ix], &[]).await.unwrap();
+
+    let (router_vault, stakers_wallet, treasury_wallet, lp_wallet) = (
+        fx.router_vault,
+        fx.sinks.stakers_wallet,
+        fx.treasury_wallet,
+        fx.lp_wallet,
+    );
+    assert_eq!(fx.balance(&router_vault).await, 0);
+    assert_eq!(fx.balance(&stakers_wallet).await, 19_305 + 193);
+    assert_eq!(fx.balance(&treasury_wallet).await, 5_197 + 52);
+    assert_eq!(fx.withheld(&stakers_wallet).await, 2);
+    assert_eq!(fx.withheld(&treasury_wallet).await, 1);
+    assert_eq!(fx.withheld(&lp_wallet).await, 1);
+    assert_eq!(fx.mint_withheld().await, 0);
+    assert_eq!(fx.router_state().await.recycled_fees, 305);
+}
+
+#[tokio::test]
+async fn harvest_updates_ledger() {
+    let mut fx = Fixture::new().await;
+    let router_vault = fx.router_vault;
+    // A prior vault balance is distributed but was not withdrawn by this run
+    fx.mint_to(&router_vault, 10_000).await;
+    let sources = fx.fee_bearing_accounts(SOURCES, TRANSFER).await;
+
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix(accounts, &sources);
+    fx.process(&[ix], &[]).await.unwrap();
+
+    let stats = fx.simulate_stats().await;
+    assert_eq!(stats, fx.router_state().await.stats);
+    assert_eq!(stats.total_harvested, 30_000);
+    assert_eq!(stats.total_withdrawn, 30_000);
+    assert_eq!(stats.harvest_runs, 1);
+    assert!(stats.last_run_slot > 0);
+
+    let paid: u64 = stats.sinks.iter().map(|sink| sink.gross).sum();
+    assert_eq!(stats.total_distributed, paid);
+    assert_eq!(paid + fx.balance(&router_vault).await, 40_000);
+    let stakers_wallet = fx.sinks.stakers_wallet;
+    let stakers = stats
+        .sinks
+        .iter()
+        .find(|sink| sink.recipient == stakers_wallet)
+        .unwrap();
+    assert_eq!(stakers.net, fx.balance(&stakers_wallet).await);
+
+    // distribute_fees pays out of the same ledger but is not a harvest run
+    fx.mint_to(&router_vault, 10_000).await;
+    let ix = fx.distribute_ix(fx.payer(), 10_000);
+    fx.process(&[ix], &[]).await.unwrap();
+    let after = fx.simulate_stats().await;
+    assert_eq!(after.harvest_runs, 1);
+    assert_eq!(after.total_distributed, paid + 10_000);
+    assert_eq!(after.sinks.len(), 3);
+}
+
+#[tokio::test]
+async fn harvest_accepts_zero_withheld_sources() {
+    let mut fx = Fixture::new().await;
+    let mut sources = fx.fee_bearing_accounts(1, TRANSFER).await;
+    // Fresh holders: valid Token-2022 accounts with nothing withheld
+    for _ in 0..2 {
+        let owner = Keypair::new().pubkey();
+        sources.push(fx.create_ata(&owner).await);
+    }
+
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix(accounts, &sources);
+    fx.process(&[ix], &[]).await.unwrap();
+    assert_eq!(fx.withheld(&sources[0]).await, 0);
+    let stats = fx.router_state().await.stats;
+    assert_eq!(stats.total_harvested, expected_fee(TRANSFER));
+    assert_eq!(stats.harvest_runs, 1);
+
+    // A run where every source is empty skips the harvest CPI entirely
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix(accounts, &sources[1..]);
+    fx.process(&[ix], &[]).await.unwrap();
+    let stats = fx.router_state().await.stats;
+    assert_eq!(stats.total_harvested, expected_fee(TRANSFER));
+    assert_eq!(stats.harvest_runs, 2);
+}
+
+#[tokio::test]
+async fn harvest_below_minimum_counts_the_run() {
+    let mut fx = Fixture::new().await;
+    let router_vault = fx.router_vault;
+    // 500 at 1% withholds 5, under MIN_DISTRIBUTE
+    let sources = fx.fee_bearing_accounts(1, 500).await;
+
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix(accounts, &sources);
+    fx.process(&[ix], &[]).await.unwrap();
+
+    assert_eq!(fx.withheld(&sources[0]).await, 0);
+    assert_eq!(fx.balance(&router_vault).await, 5);
+    assert_eq!(fx.rewards_sync_calls().await, 0);
+    let stats = fx.router_state().await.stats;
+    assert_eq!(stats.total_withdrawn, 5);
+    assert_eq!(stats.total_distributed, 0);
+    assert_eq!(stats.harvest_runs, 1);
+    assert!(stats.sinks.is_empty());
+}
+
+#[tokio::test]
+async fn harvest_without_sources_is_a_noop() {
+    let mut fx = Fixture::new().await;
+    let router_vault = fx.router_vault;
+    fx.mint_to(&router_vault, 50_000).await;
+
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix(accounts, &[]);
+    fx.process(&[ix], &[]).await.unwrap();
+
+    assert_eq!(fx.balance(&router_vault).await, 50_000);
+    assert_eq!(fx.rewards_sync_calls().await, 0);
+}
+
+#[tokio::test]
+async fn distribute_fees_pays_split_from_vault() {
+    let mut fx = Fixture::new().await;
+    let router_vault = fx.router_vault;
+    fx.mint_to(&router_vault, 50_000).await;
+
+    let ix = fx.distribute_ix(fx.payer(), 10_000);
+    fx.process(&[ix], &[]).await.unwrap();
+
+    let (stakers_wallet, treasury_wallet, lp_wallet) =
+        (fx.sinks.stakers_wallet, fx.treasury_wallet, fx.lp_wallet);
+    assert_eq!(fx.balance(&router_vault).await, 40_000);
+    assert_eq!(fx.balance(&stakers_wallet).await, 6_500 - expected_fee(6_500));
+    assert_eq!(fx.balance(&treasury_wallet).await, 1_750 - expected_fee(1_750));
+    assert_eq!(fx.balance(&lp_wallet).await, 1_750 - expected_fee(1_750));
+}
+
+#[tokio::test]
+async fn distribute_fees_keeps_remainder_in_vault() {
+    let mut fx = Fixture::new().await;
+    let router_vault = fx.router_vault;
+    fx.mint_to(&router_vault, 10_001).await;
+
+    // One more token cannot raise any net share, so it stays for next time
+    let ix = fx.distribute_ix(fx.payer(), 10_001);
+    fx.process(&[ix], &[]).await.unwrap();
+
+    let (stakers_wallet, treasury_wallet, lp_wallet) =
+        (fx.sinks.stakers_wallet, fx.treasury_wallet, fx.lp_wallet);
+    assert_eq!(fx.balance(&router_vault).await, 1);
+    assert_eq!(fx.balance(&stakers_wallet).await, 6_435);
+    assert_eq!(fx.balance(&treasury_wallet).await, 1_732);
+    assert_eq!(fx.balance(&lp_wallet).await, 1_732);
+}
+
+#[tokio::test]
+async fn crank_rejects_unauthorized_caller() {
+    let mut fx = Fixture::new().await;
+    let stranger = Keypair::new();
+    let sources = fx.fee_bearing_accounts(1, TRANSFER).await;
+
+    let accounts = fx.harvest_accounts(stranger.pubkey());
+    let ix = fx.harvest_ix(accounts, &sources);
+    assert_custom_error(
+        fx.process(&[ix], &[&stranger]).await,
+        RouterError::UnauthorizedCaller.into(),
+    );
+
+    let ix = fx.distribute_ix(stranger.pubkey(), 10_000);
+    assert_custom_error(
+        fx.process(&[ix], &[&stranger]).await,
+        RouterError::UnauthorizedCaller.into(),
+    );
+}
+
+#[tokio::test]
+async fn crank_rejects_while_paused() {
+    let mut fx = Fixture::new().await;
+    let sources = fx.fee_bearing_accounts(1, TRANSFER).await;
+    fx.pause().await;
+
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix(accounts, &sources);
+    assert_custom_error(fx.process(&[ix], &[]).await, RouterError::Paused.into());
+}
+
+#[tokio::test]
+async fn harvest_rejects_sinks_out_of_table_order() {
+    let mut fx = Fixture::new().await;
+    let sources = fx.fee_bearing_accounts(1, TRANSFER).await;
+
+    let accounts = fx.harvest_accounts(fx.payer());
+    let mut ix = fx.harvest_ix(accounts, &sources);
+    // Sinks start right after the 12 named accounts; swap treasury and LP
+    ix.accounts.swap(13, 14);
+    assert_custom_error(
+        fx.process(&[ix], &[]).await,
+        RouterError::SinkAccountMismatch.into(),
+    );
+}
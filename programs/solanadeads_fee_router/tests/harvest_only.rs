@@ -0,0 +1,70 @@
+// programs/solanadeads_fee_router/tests/harvest_only.rs
+
+//! Large holder sets are swept in chunks with `harvest_only`, then settled by
+//! one `withdraw_and_distribute`.
+
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solanadeads_fee_router::RouterError;
+use solanadeads_fee_router_test_support::{assert_custom_error, expected_fee, Fixture};
+
+const TRANSFER: u64 = 1_000_000;
+
+#[tokio::test]
+async fn chunked_sweep_settles_like_one_harvest() {
+    let mut fx = Fixture::new().await;
+    let sources = fx.fee_bearing_accounts(3, TRANSFER).await;
+
+    // Two chunks; the second carries an account that does not exist, which
+    // Token-2022 skips
+    let ix = fx.harvest_only_ix(fx.payer(), &sources[..2]);
+    fx.process(&[ix], &[]).await.unwrap();
+    let ix = fx.harvest_only_ix(fx.payer(), &[sources[2], Pubkey::new_unique()]);
+    fx.process(&[ix], &[]).await.unwrap();
+
+    let (router_vault, stakers_wallet, treasury_wallet, lp_wallet) = (
+        fx.router_vault,
+        fx.sinks.stakers_wallet,
+        fx.treasury_wallet,
+        fx.lp_wallet,
+    );
+    assert_eq!(fx.mint_withheld().await, 3 * expected_fee(TRANSFER));
+    assert_eq!(fx.balance(&router_vault).await, 0);
+    for source in &sources {
+        assert_eq!(fx.withheld(source).await, 0);
+    }
+
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.withdraw_and_distribute_ix(accounts);
+    fx.process(&[ix], &[]).await.unwrap();
+
+    // Same payout as a single harvest of all three
+    assert_eq!(fx.mint_withheld().await, 0);
+    assert_eq!(fx.balance(&router_vault).await, 0);
+    assert_eq!(fx.balance(&stakers_wallet).await, 19_305);
+    assert_eq!(fx.balance(&treasury_wallet).await, 5_197);
+    assert_eq!(fx.balance(&lp_wallet).await, 5_197);
+    assert_eq!(fx.rewards_sync_calls().await, 1);
+
+    let stats = fx.router_state().await.stats;
+    assert_eq!(stats.total_harvested, 30_000);
+    assert_eq!(stats.total_withdrawn, 30_000);
+    assert_eq!(stats.harvest_runs, 1);
+}
+
+#[tokio::test]
+async fn harvest_only_follows_crank_rules() {
+    let mut fx = Fixture::new().await;
+    let sources = fx.fee_bearing_accounts(1, TRANSFER).await;
+
+    let stranger = Keypair::new();
+    let ix = fx.harvest_only_ix(stranger.pubkey(), &sources);
+    assert_custom_error(
+        fx.process(&[ix], &[&stranger]).await,
+        RouterError::UnauthorizedCaller.into(),
+    );
+
+    fx.pause().await;
+    let ix = fx.harvest_only_ix(fx.payer(), &sources);
+    assert_custom_error(fx.process(&[ix], &[]).await, RouterError::Paused.into());
+}
@@ -0,0 +1,89 @@
+// programs/solanadeads_fee_router/tests/initialize_router.rs
+
+//! `initialize_router` admits any Token-2022 mint with a TransferFeeConfig
+//! whose withdraw authority is the mint's router PDA, and nothing else, and
+//! only sinks that seed a split table with distinct recipients.
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use solana_program_test::BanksClientError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solanadeads_fee_router::{ErrorCode, RouterError};
+use solanadeads_fee_router_test_support::{assert_custom_error, ata, router_pda, Fixture, TestMint};
+
+/// A fresh mint of `kind` and the result of initializing its router.
+async fn initialize(
+    fx: &mut Fixture,
+    kind: TestMint,
+    token_program: Pubkey,
+) -> (Pubkey, Result<(), BanksClientError>) {
+    let mint = Keypair::new();
+    fx.create_mint_with(&mint, kind).await;
+    let ix = fx.initialize_ix(mint.pubkey(), token_program);
+    (mint.pubkey(), fx.process(&[ix], &[]).await)
+}
+
+#[tokio::test]
+async fn accepts_sister_fee_mint() {
+    let mut fx = Fixture::without_router().await;
+    let mint = Keypair::new();
+    let kind = TestMint::TransferFee(Some(router_pda(&mint.pubkey())));
+    fx.create_mint_with(&mint, kind).await;
+    let ix = fx.initialize_ix(mint.pubkey(), spl_token_2022::ID);
+    fx.process(&[ix], &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn rejects_mint_without_transfer_fee_config() {
+    let mut fx = Fixture::without_router().await;
+    let (_, result) = initialize(&mut fx, TestMint::Token2022, spl_token_2022::ID).await;
+    assert_custom_error(result, RouterError::MissingTransferFeeConfig.into());
+}
+
+#[tokio::test]
+async fn rejects_other_withdraw_authority() {
+    let mut fx = Fixture::without_router().await;
+    let kind = TestMint::TransferFee(Some(Pubkey::new_unique()));
+    let (_, result) = initialize(&mut fx, kind, spl_token_2022::ID).await;
+    assert_custom_error(result, RouterError::WithdrawAuthorityMismatch.into());
+
+    let (_, result) = initialize(&mut fx, TestMint::TransferFee(None), spl_token_2022::ID).await;
+    assert_custom_error(result, RouterError::WithdrawAuthorityMismatch.into());
+}
+
+#[tokio::test]
+async fn rejects_spl_token() {
+    let mut fx = Fixture::without_router().await;
+    let (mint, result) = initialize(&mut fx, TestMint::SplToken, anchor_spl::token::ID).await;
+    assert_custom_error(result, ErrorCode::WrongTokenProgramForSink.into());
+    assert!(fx.ctx.banks_client.get_account(router_pda(&mint)).await.unwrap().is_none());
+
+    // A valid fee mint named with the SPL Token program
+    let ix = fx.initialize_ix(fx.mint, anchor_spl::token::ID);
+    assert_custom_error(
+        fx.process(&[ix], &[]).await,
+        AnchorErrorCode::ConstraintMintTokenProgram.into(),
+    );
+}
+
+#[tokio::test]
+async fn rejects_sinks_with_duplicate_recipients() {
+    let mut fx = Fixture::without_router().await;
+    let sinks = fx.sinks;
+
+    // Treasury and LP owned by the same key share an ATA
+    fx.sinks.lp_owner = sinks.treasury_owner;
+    let ix = fx.initialize_ix(fx.mint, spl_token_2022::ID);
+    assert_custom_error(fx.process(&[ix], &[]).await, RouterError::DuplicateRecipient.into());
+
+    // Stakers paid into the treasury ATA
+    fx.sinks = sinks;
+    fx.sinks.stakers_wallet = ata(&sinks.treasury_owner, &fx.mint);
+    let ix = fx.initialize_ix(fx.mint, spl_token_2022::ID);
+    assert_custom_error(fx.process(&[ix], &[]).await, RouterError::DuplicateRecipient.into());
+    assert!(fx.ctx.banks_client.get_account(fx.router).await.unwrap().is_none());
+
+    fx.sinks = sinks;
+    let ix = fx.initialize_ix(fx.mint, spl_token_2022::ID);
+    fx.process(&[ix], &[]).await.unwrap();
+}
@@ -0,0 +1,77 @@
+// programs/solanadeads_fee_router/tests/lenient_sources.rs
+
+//! With `lenient_sources` set, invalid harvest sources are dropped and the
+//! rest are still harvested; without it the first one fails the run.
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use solana_sdk::pubkey::Pubkey;
+use solanadeads_fee_router::ErrorCode;
+use solanadeads_fee_router_test_support::{assert_custom_error, expected_fee, Fixture};
+
+const TRANSFER: u64 = 1_000_000;
+
+async fn set_lenient(fx: &mut Fixture, lenient_sources: bool) {
+    let ix = fx.admin_ix(
+        solanadeads_fee_router::accounts::SetLenientSources {
+            router: fx.router,
+            authority: fx.payer(),
+            mint: fx.mint,
+        },
+        solanadeads_fee_router::instruction::SetLenientSources { lenient_sources },
+    );
+    fx.process(&[ix], &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn strict_mode_fails_on_invalid_source() {
+    let mut fx = Fixture::new().await;
+    let mut sources = fx.fee_bearing_accounts(1, TRANSFER).await;
+    sources.push(Pubkey::new_unique());
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix(accounts, &sources);
+    assert_custom_error(
+        fx.process(&[ix], &[]).await,
+        ErrorCode::WrongTokenProgramForSink.into(),
+    );
+
+    let foreign = fx.foreign_token_account().await;
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix(accounts, &[sources[0], foreign]);
+    assert_custom_error(
+        fx.process(&[ix], &[]).await,
+        ErrorCode::InvalidMintForSink.into(),
+    );
+
+    let mint = fx.mint;
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix(accounts, &[sources[0], mint]);
+    assert_custom_error(
+        fx.process(&[ix], &[]).await,
+        AnchorErrorCode::AccountDidNotDeserialize.into(),
+    );
+}
+
+#[tokio::test]
+async fn lenient_mode_skips_invalid_sources() {
+    let mut fx = Fixture::new().await;
+    set_lenient(&mut fx, true).await;
+    assert!(fx.router_state().await.lenient_sources);
+
+    let valid = fx.fee_bearing_accounts(2, TRANSFER).await;
+    let foreign = fx.foreign_token_account().await;
+    let (mint, rewards_config) = (fx.mint, fx.rewards_config);
+    // Closed, not Token-2022, not a token account, wrong mint
+    let invalid = [Pubkey::new_unique(), rewards_config, mint, foreign];
+    let mut sources = valid.clone();
+    sources.extend(invalid);
+
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix(accounts, &sources);
+    fx.process(&[ix], &[]).await.unwrap();
+    for source in &valid {
+        assert_eq!(fx.withheld(source).await, 0);
+    }
+    let stats = fx.router_state().await.stats;
+    assert_eq!(stats.total_harvested, 2 * expected_fee(TRANSFER));
+    assert_eq!(stats.harvest_runs, 1);
+}
@@ -0,0 +1,59 @@
+// programs/solanadeads_fee_router/tests/migrate_router.rs
+
+//! `migrate_router` grows a v1 router (`bump` + `authority`) to the current
+//! layout at the same PDA, after which the crank works unchanged.
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use solana_sdk::signature::{Keypair, Signer};
+use solanadeads_fee_router::{Router, RouterError, ROUTER_VERSION};
+use solanadeads_fee_router_test_support::{assert_custom_error, Fixture};
+
+#[tokio::test]
+async fn migrates_legacy_router_in_place() {
+    let mut fx = Fixture::without_router().await;
+    let payer = fx.payer();
+    fx.set_legacy_router(&payer);
+
+    let ix = fx.migrate_ix(payer);
+    fx.process(&[ix], &[]).await.unwrap();
+
+    let router = fx.router_state().await;
+    assert_eq!(router.version, ROUTER_VERSION);
+    assert_eq!(router.authority, payer);
+    assert_eq!(router.sink_config(), fx.sinks);
+    assert_eq!(router.splits.len(), 3);
+    let router_pda = fx.router;
+    assert_eq!(fx.account_data(&router_pda).await.len(), 8 + Router::LEN);
+
+    // The PDA still holds the withdraw authority, so harvesting works
+    let sources = fx.fee_bearing_accounts(1, 1_000_000).await;
+    let accounts = fx.harvest_accounts(payer);
+    let ix = fx.harvest_ix(accounts, &sources);
+    fx.process(&[ix], &[]).await.unwrap();
+    assert_eq!(fx.mint_withheld().await, 0);
+}
+
+#[tokio::test]
+async fn migrate_requires_stored_authority() {
+    let mut fx = Fixture::without_router().await;
+    let owner = Keypair::new();
+    fx.set_legacy_router(&owner.pubkey());
+
+    let ix = fx.migrate_ix(fx.payer());
+    assert_custom_error(
+        fx.process(&[ix], &[]).await,
+        AnchorErrorCode::ConstraintHasOne.into(),
+    );
+}
+
+#[tokio::test]
+async fn migrate_rejects_current_router() {
+    let mut fx = Fixture::new().await;
+    assert_eq!(fx.router_state().await.version, ROUTER_VERSION);
+
+    let ix = fx.migrate_ix(fx.payer());
+    assert_custom_error(
+        fx.process(&[ix], &[]).await,
+        RouterError::AlreadyMigrated.into(),
+    );
+}
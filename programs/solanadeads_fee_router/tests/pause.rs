@@ -0,0 +1,105 @@
+// programs/solanadeads_fee_router/tests/pause.rs
+
+//! `pause` / `unpause` by the authority or the guardian: every crank
+//! instruction fails while paused and works again once unpaused.
+
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solanadeads_fee_router::{accounts, instruction, RouterError};
+use solanadeads_fee_router_test_support::{assert_custom_error, Fixture};
+
+fn set_paused_ix(fx: &Fixture, signer: Pubkey, paused: bool) -> Instruction {
+    let accounts = accounts::SetPaused {
+        router: fx.router,
+        signer,
+        mint: fx.mint,
+    };
+    if paused {
+        fx.admin_ix(accounts, instruction::Pause {})
+    } else {
+        fx.admin_ix(accounts, instruction::Unpause {})
+    }
+}
+
+async fn set_guardian(fx: &mut Fixture, guardian: Pubkey) {
+    let ix = fx.admin_ix(
+        accounts::SetGuardian {
+            router: fx.router,
+            authority: fx.payer(),
+            mint: fx.mint,
+        },
+        instruction::SetGuardian { guardian },
+    );
+    fx.process(&[ix], &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn guardian_pauses_and_unpauses() {
+    let mut fx = Fixture::new().await;
+    let guardian = Keypair::new();
+    set_guardian(&mut fx, guardian.pubkey()).await;
+
+    let ix = set_paused_ix(&fx, guardian.pubkey(), true);
+    fx.process(&[ix], &[&guardian]).await.unwrap();
+    assert!(fx.router_state().await.paused);
+
+    let ix = set_paused_ix(&fx, guardian.pubkey(), false);
+    fx.process(&[ix], &[&guardian]).await.unwrap();
+    assert!(!fx.router_state().await.paused);
+
+    // Cleared again, the guardian key is a stranger
+    set_guardian(&mut fx, Pubkey::default()).await;
+    let ix = set_paused_ix(&fx, guardian.pubkey(), true);
+    assert_custom_error(
+        fx.process(&[ix], &[&guardian]).await,
+        RouterError::NotAuthorityOrGuardian.into(),
+    );
+}
+
+#[tokio::test]
+async fn pause_rejects_stranger() {
+    let mut fx = Fixture::new().await;
+    set_guardian(&mut fx, Pubkey::new_unique()).await;
+    let stranger = Keypair::new();
+    for paused in [true, false] {
+        let ix = set_paused_ix(&fx, stranger.pubkey(), paused);
+        assert_custom_error(
+            fx.process(&[ix], &[&stranger]).await,
+            RouterError::NotAuthorityOrGuardian.into(),
+        );
+    }
+    assert!(!fx.router_state().await.paused);
+}
+
+#[tokio::test]
+async fn paused_router_rejects_every_crank_until_unpaused() {
+    let mut fx = Fixture::new().await;
+    let sources = fx.fee_bearing_accounts(1, 1_000_000).await;
+    let router_vault = fx.router_vault;
+    fx.mint_to(&router_vault, 10_000).await;
+    fx.pause().await;
+
+    let ix = fx.distribute_ix(fx.payer(), 10_000);
+    assert_custom_error(fx.process(&[ix], &[]).await, RouterError::Paused.into());
+    let ix = fx.harvest_only_ix(fx.payer(), &sources);
+    assert_custom_error(fx.process(&[ix], &[]).await, RouterError::Paused.into());
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix(accounts, &sources);
+    assert_custom_error(fx.process(&[ix], &[]).await, RouterError::Paused.into());
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.withdraw_and_distribute_ix(accounts);
+    assert_custom_error(fx.process(&[ix], &[]).await, RouterError::Paused.into());
+
+    let ix = set_paused_ix(&fx, fx.payer(), false);
+    fx.process(&[ix], &[]).await.unwrap();
+
+    let ix = fx.distribute_ix(fx.payer(), 10_000);
+    fx.process(&[ix], &[]).await.unwrap();
+    assert_eq!(fx.balance(&router_vault).await, 0);
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix(accounts, &sources);
+    fx.process(&[ix], &[]).await.unwrap();
+    assert_eq!(fx.withheld(&sources[0]).await, 0);
+    assert_eq!(fx.router_state().await.stats.harvest_runs, 1);
+}
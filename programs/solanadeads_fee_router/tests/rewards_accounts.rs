@@ -0,0 +1,135 @@
+// programs/solanadeads_fee_router/tests/rewards_accounts.rs
+
+//! `harvest_and_distribute` must reject any substitution of the accounts it
+//! hands to the rewards-program CPI, and any run that omits them while the
+//! CPI is enabled. It still succeeds when the CPI is disabled.
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use solana_sdk::pubkey::Pubkey;
+use solanadeads_fee_router::{RewardsCpiConfig, RouterError};
+use solanadeads_fee_router_test_support::{assert_custom_error, Fixture};
+
+#[tokio::test]
+async fn accepts_canonical_rewards_accounts() {
+    let mut fx = Fixture::new().await;
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix(accounts, &[]);
+    fx.process(&[ix], &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn rejects_substituted_rewards_program() {
+    let mut fx = Fixture::new().await;
+    let mut accounts = fx.harvest_accounts(fx.payer());
+    // Any other executable account, e.g. Token-2022 itself
+    accounts.rewards_program = Some(spl_token_2022::ID);
+    let ix = fx.harvest_ix(accounts, &[]);
+    assert_custom_error(
+        fx.process(&[ix], &[]).await,
+        RouterError::InvalidRewardsProgram.into(),
+    );
+}
+
+#[tokio::test]
+async fn rejects_substituted_rewards_config() {
+    let mut fx = Fixture::new().await;
+    let mut accounts = fx.harvest_accounts(fx.payer());
+    accounts.rewards_config = Some(fx.pool_registry);
+    let ix = fx.harvest_ix(accounts, &[]);
+    assert_custom_error(
+        fx.process(&[ix], &[]).await,
+        AnchorErrorCode::ConstraintSeeds.into(),
+    );
+}
+
+#[tokio::test]
+async fn rejects_substituted_pool_registry() {
+    let mut fx = Fixture::new().await;
+    let mut accounts = fx.harvest_accounts(fx.payer());
+    accounts.pool_registry = Some(fx.rewards_config);
+    let ix = fx.harvest_ix(accounts, &[]);
+    assert_custom_error(
+        fx.process(&[ix], &[]).await,
+        AnchorErrorCode::ConstraintSeeds.into(),
+    );
+}
+
+#[tokio::test]
+async fn rejects_substituted_vault_authority() {
+    let mut fx = Fixture::new().await;
+    let mut accounts = fx.harvest_accounts(fx.payer());
+    accounts.vault_authority_pda = Some(Pubkey::new_unique());
+    let ix = fx.harvest_ix(accounts, &[]);
+    assert_custom_error(
+        fx.process(&[ix], &[]).await,
+        AnchorErrorCode::ConstraintSeeds.into(),
+    );
+}
+
+#[tokio::test]
+async fn harvest_calls_rewards_sync_when_enabled() {
+    let mut fx = Fixture::new().await;
+    let sources = fx.fee_bearing_accounts(2, 1_000_000).await;
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix(accounts, &sources);
+    fx.process(&[ix], &[]).await.unwrap();
+    assert_eq!(fx.rewards_sync_calls().await, 1);
+    assert!(fx.rewards_sync_pool_ids().await.is_empty());
+}
+
+#[tokio::test]
+async fn harvest_forwards_pool_ids_to_rewards_sync() {
+    let mut fx = Fixture::new().await;
+    let sources = fx.fee_bearing_accounts(1, 1_000_000).await;
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix_with_pool_ids(accounts, &sources, vec![2, 5]);
+    fx.process(&[ix], &[]).await.unwrap();
+    assert_eq!(fx.rewards_sync_pool_ids().await, vec![2, 5]);
+}
+
+#[tokio::test]
+async fn harvest_skips_rewards_sync_when_disabled() {
+    let mut fx = Fixture::new().await;
+    fx.update_rewards_cpi(RewardsCpiConfig {
+        enabled: false,
+        ..RewardsCpiConfig::default()
+    })
+    .await;
+    let sources = fx.fee_bearing_accounts(2, 1_000_000).await;
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix(accounts, &sources);
+    fx.process(&[ix], &[]).await.unwrap();
+    assert_eq!(fx.rewards_sync_calls().await, 0);
+    let stakers_wallet = fx.sinks.stakers_wallet;
+    assert!(fx.balance(&stakers_wallet).await > 0);
+}
+
+#[tokio::test]
+async fn harvest_requires_rewards_accounts_when_enabled() {
+    let mut fx = Fixture::new().await;
+    let sources = fx.fee_bearing_accounts(2, 1_000_000).await;
+    let without_rewards = |fx: &Fixture| {
+        let mut accounts = fx.harvest_accounts(fx.payer());
+        accounts.rewards_program = None;
+        accounts.rewards_config = None;
+        accounts.pool_registry = None;
+        accounts.vault_authority_pda = None;
+        accounts
+    };
+    let ix = fx.harvest_ix(without_rewards(&fx), &sources);
+    assert_custom_error(
+        fx.process(&[ix], &[]).await,
+        RouterError::MissingRewardsAccounts.into(),
+    );
+    assert!(fx.withheld(&sources[0]).await > 0);
+
+    // Omitting them is fine once the authority turns the sync off
+    fx.update_rewards_cpi(RewardsCpiConfig {
+        enabled: false,
+        ..RewardsCpiConfig::default()
+    })
+    .await;
+    let ix = fx.harvest_ix(without_rewards(&fx), &sources);
+    fx.process(&[ix], &[]).await.unwrap();
+    assert_eq!(fx.rewards_sync_calls().await, 0);
+}
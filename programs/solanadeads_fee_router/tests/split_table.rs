@@ -0,0 +1,107 @@
+// programs/solanadeads_fee_router/tests/split_table.rs
+
+//! `update_splits` is authority-only (the table rules themselves are unit
+//! tested on `validate_splits`), and a full `MAX_SPLIT_RECIPIENTS` table is
+//! paid through `remaining_accounts` by both payout paths. Only balances are
+//! checked: the router runs natively here, so its compute cost is unmeasured.
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use solana_sdk::signature::{Keypair, Signer};
+use solanadeads_fee_router::{SplitRecipient, BPS_DENOMINATOR, MAX_SPLIT_RECIPIENTS};
+use solanadeads_fee_router_test_support::{assert_custom_error, Fixture};
+
+#[tokio::test]
+async fn update_splits_requires_authority() {
+    let mut fx = Fixture::new().await;
+    let before = fx.router_state().await.splits;
+    let splits = vec![SplitRecipient {
+        recipient: fx.treasury_wallet,
+        bps: BPS_DENOMINATOR,
+    }];
+
+    let stranger = Keypair::new();
+    let ix = fx.update_splits_ix(stranger.pubkey(), splits.clone());
+    assert_custom_error(
+        fx.process(&[ix], &[&stranger]).await,
+        AnchorErrorCode::ConstraintHasOne.into(),
+    );
+    assert_eq!(fx.router_state().await.splits, before);
+
+    let ix = fx.update_splits_ix(fx.payer(), splits.clone());
+    fx.process(&[ix], &[]).await.unwrap();
+    assert_eq!(fx.router_state().await.splits, splits);
+}
+
+/// Eight recipients summing to 10_000 bps: the default three sinks plus
+/// five extra wallets (marketing, buyback, burn, dev, reserve).
+const EIGHT_WAY_BPS: [u16; MAX_SPLIT_RECIPIENTS] = [4_000, 1_500, 1_500, 1_000, 1_000, 500, 300, 200];
+
+async fn eight_way_fixture() -> Fixture {
+    let mut fx = Fixture::new().await;
+    let mut recipients = vec![fx.sinks.stakers_wallet, fx.treasury_wallet, fx.lp_wallet];
+    for _ in 3..MAX_SPLIT_RECIPIENTS {
+        let owner = Keypair::new().pubkey();
+        recipients.push(fx.create_ata(&owner).await);
+    }
+    let splits = recipients
+        .into_iter()
+        .zip(EIGHT_WAY_BPS)
+        .map(|(recipient, bps)| SplitRecipient { recipient, bps })
+        .collect();
+    fx.update_splits(splits).await;
+    fx
+}
+
+async fn sink_balances(fx: &mut Fixture) -> Vec<u64> {
+    let mut out = Vec::new();
+    for sink in fx.split_sinks.clone() {
+        out.push(fx.balance(&sink).await);
+    }
+    out
+}
+
+#[tokio::test]
+async fn distribute_fees_pays_full_table() {
+    let mut fx = eight_way_fixture().await;
+    let router_vault = fx.router_vault;
+    fx.mint_to(&router_vault, 100_000).await;
+
+    let ix = fx.distribute_ix(fx.payer(), 100_000);
+    fx.process(&[ix], &[]).await.unwrap();
+
+    // Net total 99_002 splits exactly; each leg grosses up by 1%
+    // (40_000 / 15_000 / 15_000 / 10_000 / 10_000 / 5_000 / 3_000 / 2_000)
+    assert_eq!(fx.balance(&router_vault).await, 0);
+    assert_eq!(
+        sink_balances(&mut fx).await,
+        vec![39_600, 14_850, 14_850, 9_900, 9_900, 4_950, 2_970, 1_980]
+    );
+    // Every gross is a multiple of 100, so the 1% fee is exactly net / 99
+    for sink in fx.split_sinks.clone() {
+        let net = fx.balance(&sink).await;
+        assert_eq!(fx.withheld(&sink).await, net / 99);
+    }
+    assert_eq!(fx.router_state().await.stats.sinks.len(), MAX_SPLIT_RECIPIENTS);
+}
+
+#[tokio::test]
+async fn harvest_pays_full_table() {
+    let mut fx = eight_way_fixture().await;
+    let sources = fx.fee_bearing_accounts(3, 1_000_000).await;
+
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.harvest_ix(accounts, &sources);
+    fx.process(&[ix], &[]).await.unwrap();
+
+    // 30_000 harvested: net total 29_702, grossed up to exactly 30_000
+    // (12_000 / 4_500 / 4_500 / 3_000 / 3_000 / 1_500 / 900 / 600)
+    let router_vault = fx.router_vault;
+    assert_eq!(fx.balance(&router_vault).await, 0);
+    assert_eq!(
+        sink_balances(&mut fx).await,
+        vec![11_880, 4_455, 4_455, 2_970, 2_970, 1_485, 891, 594]
+    );
+    assert_eq!(fx.mint_withheld().await, 0);
+    assert_eq!(fx.rewards_sync_calls().await, 1);
+    assert_eq!(fx.router_state().await.recycled_fees, 300);
+}
@@ -0,0 +1,51 @@
+// programs/solanadeads_fee_router/tests/update_sinks.rs
+
+//! `update_sinks` re-points split table entries from a replaced sink to its
+//! replacement, keeping the weights, and payouts follow.
+
+use solana_sdk::pubkey::Pubkey;
+use solanadeads_fee_router::{accounts, instruction, RouterError, SinkConfig, TREASURY_BP};
+use solanadeads_fee_router_test_support::{assert_custom_error, ata, expected_fee, Fixture};
+
+#[tokio::test]
+async fn rotated_treasury_receives_its_share() {
+    let mut fx = Fixture::new().await;
+    let old_treasury = fx.treasury_wallet;
+    let before = fx.router_state().await.splits;
+
+    let new_owner = Pubkey::new_unique();
+    let new_treasury = fx.create_ata(&new_owner).await;
+    let sinks = SinkConfig {
+        treasury_owner: new_owner,
+        ..fx.sinks
+    };
+    let ix = fx.admin_ix(
+        accounts::UpdateSinks {
+            router: fx.router,
+            authority: fx.payer(),
+            mint: fx.mint,
+        },
+        instruction::UpdateSinks { sinks },
+    );
+    fx.process(&[ix], &[]).await.unwrap();
+
+    let router = fx.router_state().await;
+    assert_eq!(router.sink_config(), sinks);
+    assert_eq!(new_treasury, ata(&new_owner, &fx.mint));
+    // Only the treasury entry moved; order and weights are unchanged
+    assert_eq!(router.splits[1].recipient, new_treasury);
+    assert_eq!(router.splits[1].bps, TREASURY_BP);
+    assert_eq!((router.splits[0], router.splits[2]), (before[0], before[2]));
+
+    let router_vault = fx.router_vault;
+    fx.mint_to(&router_vault, 10_000).await;
+    // The old treasury account no longer matches the table
+    let ix = fx.distribute_ix(fx.payer(), 10_000);
+    assert_custom_error(fx.process(&[ix], &[]).await, RouterError::SinkAccountMismatch.into());
+
+    fx.split_sinks[1] = new_treasury;
+    let ix = fx.distribute_ix(fx.payer(), 10_000);
+    fx.process(&[ix], &[]).await.unwrap();
+    assert_eq!(fx.balance(&new_treasury).await, 1_750 - expected_fee(1_750));
+    assert_eq!(fx.balance(&old_treasury).await, 0);
+}
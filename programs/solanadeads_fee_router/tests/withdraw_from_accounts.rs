@@ -0,0 +1,76 @@
+// programs/solanadeads_fee_router/tests/withdraw_from_accounts.rs
+
+//! `withdraw_from_accounts_and_distribute` pulls withheld fees straight from
+//! the holder accounts into the vault, with the router PDA as withdraw
+//! authority, and pays the same split as a harvest through the mint.
+
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solanadeads_fee_router::{ErrorCode, RouterError};
+use solanadeads_fee_router_test_support::{assert_custom_error, expected_fee, Fixture};
+
+const TRANSFER: u64 = 1_000_000;
+
+#[tokio::test]
+async fn withdraws_from_accounts_and_pays_sinks() {
+    let mut fx = Fixture::new().await;
+    let mut sources = fx.fee_bearing_accounts(3, TRANSFER).await;
+    // A valid holder with nothing withheld is left out of the CPI
+    let owner = Keypair::new().pubkey();
+    sources.push(fx.create_ata(&owner).await);
+
+    // Fees already harvested to the mint stay there
+    let parked = fx.fee_bearing_accounts(1, TRANSFER).await;
+    let ix = fx.harvest_only_ix(fx.payer(), &parked);
+    fx.process(&[ix], &[]).await.unwrap();
+
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.withdraw_from_accounts_ix(accounts, &sources);
+    fx.process(&[ix], &[]).await.unwrap();
+
+    let (router_vault, stakers_wallet, treasury_wallet, lp_wallet) = (
+        fx.router_vault,
+        fx.sinks.stakers_wallet,
+        fx.treasury_wallet,
+        fx.lp_wallet,
+    );
+    for source in &sources {
+        assert_eq!(fx.withheld(source).await, 0);
+    }
+    assert_eq!(fx.mint_withheld().await, expected_fee(TRANSFER));
+
+    // Same payout as harvesting the three through the mint
+    assert_eq!(fx.balance(&router_vault).await, 0);
+    assert_eq!(fx.balance(&stakers_wallet).await, 19_305);
+    assert_eq!(fx.balance(&treasury_wallet).await, 5_197);
+    assert_eq!(fx.balance(&lp_wallet).await, 5_197);
+    assert_eq!(fx.rewards_sync_calls().await, 1);
+
+    let stats = fx.router_state().await.stats;
+    assert_eq!(stats.total_harvested, expected_fee(TRANSFER));
+    assert_eq!(stats.total_withdrawn, 30_000);
+    assert_eq!(stats.harvest_runs, 1);
+}
+
+#[tokio::test]
+async fn withdraw_from_accounts_checks_sources_and_crank() {
+    let mut fx = Fixture::new().await;
+    let mut sources = fx.fee_bearing_accounts(1, TRANSFER).await;
+
+    let stranger = Keypair::new();
+    let accounts = fx.harvest_accounts(stranger.pubkey());
+    let ix = fx.withdraw_from_accounts_ix(accounts, &sources);
+    assert_custom_error(
+        fx.process(&[ix], &[&stranger]).await,
+        RouterError::UnauthorizedCaller.into(),
+    );
+
+    sources.push(Pubkey::new_unique());
+    let accounts = fx.harvest_accounts(fx.payer());
+    let ix = fx.withdraw_from_accounts_ix(accounts, &sources);
+    assert_custom_error(
+        fx.process(&[ix], &[]).await,
+        ErrorCode::WrongTokenProgramForSink.into(),
+    );
+    assert_eq!(fx.withheld(&sources[0]).await, expected_fee(TRANSFER));
+}